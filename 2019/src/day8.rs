@@ -7,7 +7,7 @@ pub(crate) fn main() -> io::Result<()> {
     let (part1, part2) = day8();
 
     println!("day8::part1: {}", part1);
-    println!("day8::part2:\n{}", part2);
+    println!("day8::part2: {}", part2);
 
     Ok(())
 }
@@ -41,7 +41,8 @@ fn part2(s: &str) -> String {
     let width = 25;
     let height = 6;
 
-    render_input(s, width, height)
+    let image = render_input(s, width, height);
+    aoc_ocr::recognise_art(&image, '#').unwrap_or(image)
 }
 
 fn count_pixel(layer: LayerRef, pixel: Pixel) -> usize {
@@ -121,8 +122,6 @@ fn render_pixel(pixel: &Pixel) -> char {
 mod tests {
     use super::*;
 
-    use indoc::indoc;
-
     #[test]
     fn test_part2_example1() {
         let input = "0222112222120000";
@@ -145,13 +144,6 @@ mod tests {
 
     #[test]
     fn test_day8() {
-        let part2 = indoc!("
-             ##    ## #### #  # ###  
-            #  #    #    # #  # #  # 
-            #       #   #  #### #  # 
-            #       #  #   #  # ###  
-            #  # #  # #    #  # # #  
-             ##   ##  #### #  # #  # ");
-        assert_eq!(day8(), (2159, part2.into()))
+        assert_eq!(day8(), (2159, "CJZHR".into()))
     }
 }
@@ -1,43 +1,144 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use itertools::Itertools;
 
-use crate::lib::{
-    self,
-    Error
-};
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(input: &[u32]) -> Result<u32> {
-    for combination in input.into_iter().combinations(2) {
-        if combination.iter().map(|x| **x).sum::<u32>() == 2020 {
-            return Ok(combination.iter().map(|x| **x).product())
-        }
+    let combination = k_sum(input, 2, 2020, Find::First)?;
+    Ok(combination[0].iter().product())
+}
+
+fn part2(input: &[u32]) -> Result<u32> {
+    let combination = k_sum(input, 3, 2020, Find::First)?;
+    Ok(combination[0].iter().product())
+}
+
+/// Runs the `ksum <k> [<target>]` command, which lists every combination of `k` entries that
+/// sum to the target, 2020 by default.
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    let (k, target) = match args {
+        ["ksum", k] => (k, "2020"),
+        ["ksum", k, target] => (k, *target),
+        _ => bail!("Usage: ksum K [TARGET]"),
+    };
+    let k = k.parse().with_context(|| format!("Invalid k: '{}'", k))?;
+    let target = target.parse().with_context(|| format!("Invalid target: '{}'", target))?;
+    let data = lib::parse_input(input)?;
+
+    let combinations = k_sum(&data, k, target, Find::All)?;
+    let lines = combinations.iter()
+        .map(|combination| combination.iter().join(" + ") + "\n")
+        .collect();
+
+    Ok(lines)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Find {
+    First,
+    All,
+}
+
+/// Finds combinations of `k` entries that sum to `target`.
+///
+/// Each combination is returned in ascending order and no combination of values is returned
+/// twice.  The entries are sorted so that the last two entries of a combination can be found
+/// by narrowing a pair of pointers from both ends.
+fn k_sum(input: &[u32], k: usize, target: u32, find: Find) -> Result<Vec<Vec<u32>>> {
+    let mut sorted = input.to_vec();
+    sorted.sort_unstable();
+
+    let mut combinations = Vec::new();
+    k_sum_sorted(&sorted, k, target as u64, find, &mut Vec::new(), &mut combinations);
+
+    if combinations.is_empty() {
+        Err(Error::NoSolution)?
     }
 
-    Err(Error::NoSolution.into())
+    Ok(combinations)
 }
 
-fn part2(input: &[u32]) -> Result<u32> {
-    for combination in input.into_iter().combinations(3) {
-        if combination.iter().map(|x| **x).sum::<u32>() == 2020 {
-            return Ok(combination.iter().map(|x| **x).product())
+/// Returns true when the search should stop.
+fn k_sum_sorted(
+    sorted: &[u32],
+    k: usize,
+    target: u64,
+    find: Find,
+    prefix: &mut Vec<u32>,
+    combinations: &mut Vec<Vec<u32>>,
+) -> bool {
+    let mut found = |prefix: &[u32], rest: &[u32]| {
+        combinations.push(prefix.iter().chain(rest).copied().collect());
+        find == Find::First
+    };
+
+    match k {
+        0 => target == 0 && found(prefix, &[]),
+        1 => target <= u32::MAX as u64
+            && sorted.binary_search(&(target as u32)).is_ok()
+            && found(prefix, &[target as u32]),
+        2 => {
+            if sorted.len() < 2 {
+                return false;
+            }
+
+            let (mut lo, mut hi) = (0, sorted.len() - 1);
+            while lo < hi {
+                let sum = sorted[lo] as u64 + sorted[hi] as u64;
+                if sum < target {
+                    lo += 1;
+                } else if sum > target {
+                    hi -= 1;
+                } else {
+                    if found(prefix, &[sorted[lo], sorted[hi]]) {
+                        return true;
+                    }
+                    let (lo_value, hi_value) = (sorted[lo], sorted[hi]);
+                    while lo < hi && sorted[lo] == lo_value { lo += 1; }
+                    while lo < hi && sorted[hi] == hi_value { hi -= 1; }
+                }
+            }
+
+            false
         }
-    }
+        _ => {
+            for idx in 0..sorted.len() {
+                let value = sorted[idx];
+                if idx > 0 && sorted[idx - 1] == value {
+                    continue;
+                }
+                if value as u64 * k as u64 > target {
+                    break;
+                }
 
-    Err(Error::NoSolution.into())
+                prefix.push(value);
+                let stop = k_sum_sorted(&sorted[idx + 1..], k - 1, target - value as u64, find, prefix, combinations);
+                prefix.pop();
+
+                if stop {
+                    return true;
+                }
+            }
+
+            false
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use super::lib;
+    use super::{lib, Find};
 
     fn data() -> Vec<u32> {
         let input = indoc!{"
@@ -60,4 +161,44 @@ mod test {
     fn part2() {
         assert_eq!(super::part2(&data()).unwrap(), 241861950);
     }
+
+    #[test]
+    fn k_sum_all() {
+        let actual = super::k_sum(&data(), 2, 2020, Find::All).unwrap();
+        assert_eq!(actual, vec![vec![299, 1721]]);
+
+        let input = [1, 2, 3, 4, 5, 6, 3];
+        let actual = super::k_sum(&input, 3, 9, Find::All).unwrap();
+        assert_eq!(actual, vec![vec![1, 2, 6], vec![1, 3, 5], vec![2, 3, 4]]);
+
+        let actual = super::k_sum(&input, 4, 14, Find::All).unwrap();
+        assert_eq!(actual, vec![vec![1, 2, 5, 6], vec![1, 3, 4, 6], vec![2, 3, 3, 6], vec![2, 3, 4, 5]]);
+    }
+
+    #[test]
+    fn tool() {
+        let input = "1721\n979\n366\n299\n675\n1456\n";
+        assert_eq!(super::tool(input, &["ksum", "2"]).unwrap(), "299 + 1721\n");
+        assert_eq!(super::tool(input, &["ksum", "2", "1345"]).unwrap(), "366 + 979\n");
+        assert!(super::tool(input, &["ksum", "2", "1"]).is_err());
+        assert!(super::tool(input, &["ksum", "two"]).is_err());
+    }
+
+    #[test]
+    fn k_sum_first() {
+        let input = [1, 2, 3, 4, 5, 6];
+        let actual = super::k_sum(&input, 3, 9, Find::First).unwrap();
+        assert_eq!(actual, vec![vec![1, 2, 6]]);
+
+        let actual = super::k_sum(&input, 1, 4, Find::First).unwrap();
+        assert_eq!(actual, vec![vec![4]]);
+    }
+
+    #[test]
+    fn k_sum_no_solution() {
+        let input = [1, 2, 3];
+        assert!(super::k_sum(&input, 2, 6, Find::All).is_err());
+        assert!(super::k_sum(&input, 4, 6, Find::All).is_err());
+        assert!(super::k_sum(&[], 2, 0, Find::First).is_err());
+    }
 }
@@ -1,42 +1,134 @@
+use std::collections::HashSet;
 use std::str::FromStr;
 
 use anyhow::Result;
+use regex::Regex;
 
-use crate::lib::{self, Error};
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(data: &[Entry]) -> Result<usize> {
-    let count = data
-        .iter()
-        .map(|entry| (
-            entry,
-            entry.password.chars().filter(|c| c == &entry.c).count()
-        ))
-        .filter(|(entry, count)| count >= &entry.min && count <= &entry.max )
-        .count();
-
-    Ok(count)
+    Ok(count_valid(data, policy("count")?.as_ref()))
 }
 
 fn part2(data: &[Entry]) -> Result<usize> {
-    let count = data
+    Ok(count_valid(data, policy("positions")?.as_ref()))
+}
+
+fn count_valid(data: &[Entry], policy: &dyn Policy) -> usize {
+    data
         .iter()
-        .map(|entry| (
-            entry.password.chars().nth(entry.min - 1).unwrap(),
-            entry.password.chars().nth(entry.max - 1).unwrap(),
-            entry.c
-        ))
-        .filter(|(c0, c1, c)| (c == c0) ^ (c == c1))
-        .count();
-
-    Ok(count)
+        .filter(|entry| policy.check(entry).is_ok())
+        .count()
+}
+
+/// A rule for deciding whether the password of an entry is valid.
+///
+/// A rejected password is reported with the reason it was rejected.
+trait Policy {
+    fn check(&self, entry: &Entry) -> Result<(), String>;
+}
+
+/// Returns the policy with the given name.
+///
+/// The policies that take an argument are named `<name>:<argument>`.
+fn policy(name: &str) -> Result<Box<dyn Policy>> {
+    let (name, arg) = match name.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (name, None),
+    };
+
+    let policy: Box<dyn Policy> = match (name, arg) {
+        ("count", None) => Box::new(Count),
+        ("positions", None) => Box::new(Positions),
+        ("forbidden", Some(chars)) => Box::new(Forbidden(chars.into())),
+        ("distinct", Some(min)) => Box::new(Distinct(min.parse()?)),
+        ("regex", Some(pattern)) => Box::new(Pattern(Regex::new(pattern)?)),
+        _ => Err(anyhow::Error::new(Error::ParseError).context(format!("Unknown policy: '{}'", name)))?,
+    };
+
+    Ok(policy)
+}
+
+/// The letter appears between `min` and `max` times.
+struct Count;
+
+impl Policy for Count {
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let count = entry.password.chars().filter(|c| c == &entry.c).count();
+        if count >= entry.min && count <= entry.max {
+            Ok(())
+        } else {
+            Err(format!("'{}' appears {} times, not {}-{}", entry.c, count, entry.min, entry.max))
+        }
+    }
+}
+
+/// The letter appears at exactly one of the 1-based positions `min` and `max`.
+struct Positions;
+
+impl Policy for Positions {
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let at = |position: usize| position.checked_sub(1)
+            .and_then(|idx| entry.password.chars().nth(idx))
+            .ok_or_else(|| format!("position {} is outside the password", position));
+        let c0 = at(entry.min)?;
+        let c1 = at(entry.max)?;
+
+        match (c0 == entry.c, c1 == entry.c) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(format!("'{}' is at both positions {} and {}", entry.c, entry.min, entry.max)),
+            (false, false) => Err(format!("'{}' is at neither position {} nor {}", entry.c, entry.min, entry.max)),
+        }
+    }
+}
+
+/// None of the characters appear in the password.
+struct Forbidden(String);
+
+impl Policy for Forbidden {
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        match entry.password.chars().find(|c| self.0.contains(*c)) {
+            Some(c) => Err(format!("'{}' is forbidden", c)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The password has at least this many distinct letters.
+struct Distinct(usize);
+
+impl Policy for Distinct {
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        let count = entry.password.chars().collect::<HashSet<char>>().len();
+        if count >= self.0 {
+            Ok(())
+        } else {
+            Err(format!("{} distinct letters, fewer than {}", count, self.0))
+        }
+    }
+}
+
+/// The password matches the pattern.
+struct Pattern(Regex);
+
+impl Policy for Pattern {
+    fn check(&self, entry: &Entry) -> Result<(), String> {
+        if self.0.is_match(&entry.password) {
+            Ok(())
+        } else {
+            Err(format!("does not match '{}'", self.0.as_str()))
+        }
+    }
 }
 
 struct Entry {
@@ -69,6 +161,7 @@ impl FromStr for Entry {
 mod test {
     use indoc::indoc;
     use super::{lib, Entry};
+    use super::policy;
 
     fn data() -> Vec<Entry> {
         let input = indoc!{"
@@ -88,4 +181,36 @@ mod test {
     fn part2() {
         assert_eq!(super::part2(&data()).unwrap(), 1);
     }
+
+    #[test]
+    fn rejections() {
+        let data = data();
+        let check = |name: &str, entry: &Entry| policy(name).unwrap().check(entry);
+
+        assert_eq!(check("count", &data[0]), Ok(()));
+        assert_eq!(check("count", &data[1]), Err("'b' appears 0 times, not 1-3".into()));
+        assert_eq!(check("positions", &data[1]), Err("'b' is at neither position 1 nor 3".into()));
+        assert_eq!(check("positions", &data[2]), Err("'c' is at both positions 2 and 9".into()));
+        assert_eq!(check("forbidden:xyz", &data[0]), Ok(()));
+        assert_eq!(check("forbidden:ed", &data[0]), Err("'d' is forbidden".into()));
+        assert_eq!(check("distinct:5", &data[1]), Ok(()));
+        assert_eq!(check("distinct:2", &data[2]), Err("1 distinct letters, fewer than 2".into()));
+        assert_eq!(check("regex:^c+$", &data[2]), Ok(()));
+        assert_eq!(check("regex:^a", &data[1]), Err("does not match '^a'".into()));
+    }
+
+    #[test]
+    fn position_out_of_range() {
+        let entry = "1-9 a: abc".parse::<Entry>().unwrap();
+        let actual = policy("positions").unwrap().check(&entry);
+        assert_eq!(actual, Err("position 9 is outside the password".into()));
+    }
+
+    #[test]
+    fn unknown_policy() {
+        assert!(policy("length").is_err());
+        assert!(policy("count:1").is_err());
+        assert!(policy("distinct").is_err());
+        assert!(policy("distinct:x").is_err());
+    }
 }
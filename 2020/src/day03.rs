@@ -1,19 +1,23 @@
+use std::collections::HashSet;
+use std::fmt;
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
-use crate::lib;
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
-    let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+pub fn run(input: &str, part: Part) -> Result<String> {
+    let data = parse_map(input)?;
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(data: &[Entry]) -> Result<usize> {
-    Ok(trees_on_slope(data, 3, 1))
+    trees_on_slope(data, 3, 1)
 }
 
 fn part2(data: &[Entry]) -> Result<usize> {
@@ -25,33 +29,153 @@ fn part2(data: &[Entry]) -> Result<usize> {
         (1, 2),
     ];
 
-    Ok(
-        slopes.iter()
-            .map(|(dx, dy)| trees_on_slope(data, *dx, *dy))
-            .product()
-    )
+    slopes.iter()
+        .map(|(dx, dy)| trees_on_slope(data, *dx, *dy))
+        .product()
 }
 
-fn trees_on_slope(data: &[Entry], dx: usize, dy: usize) -> usize {
-    let width = data[0].map.len();
-    let (count, _) = data.iter()
+/// Runs one of the slope commands:
+///
+/// * `slopes [<bound>]`: counts the trees on every slope with `dx` and `dy` no greater than the
+///   bound, 7 by default
+/// * `path <dx> <dy>`: renders the path taken by a slope
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    let data = parse_map(input)?;
+    match args {
+        ["slopes"] => Ok(SlopeReport::explore(&data, 7)?.to_string()),
+        ["slopes", bound] => Ok(SlopeReport::explore(&data, parse_arg(bound)?)?.to_string()),
+        ["path", dx, dy] => render_path(&data, parse_arg(dx)?, parse_arg(dy)?),
+        _ => bail!("Usage: slopes [BOUND] | path DX DY"),
+    }
+}
+
+fn parse_arg(arg: &str) -> Result<usize> {
+    arg.parse().with_context(|| format!("Invalid number: '{}'", arg))
+}
+
+fn trees_on_slope(data: &[Entry], dx: usize, dy: usize) -> Result<usize> {
+    let trees = path(data, dx, dy)?
+        .filter(|&(x, y)| data[y].is_tree(x))
+        .count();
+
+    Ok(trees)
+}
+
+/// Returns the positions visited going `dx` right and `dy` down from the top left, or an
+/// error if `dy` is zero since the path would never leave the first row.
+///
+/// The x positions are not wrapped to the width of the map.
+fn path(data: &[Entry], dx: usize, dy: usize) -> Result<impl Iterator<Item = (usize, usize)>> {
+    if dy == 0 {
+        bail!("Slope ({}, {}) does not move down", dx, dy);
+    }
+
+    let path = (0..data.len())
+        .step_by(dy)
         .enumerate()
-        .filter_map(|(i, entry)|
-            if i % dy == 0 {
-                Some(entry)
-            } else {
-                None
-            }
+        .map(move |(step, y)| (step * dx, y));
+
+    Ok(path)
+}
+
+/// Tree counts for every explored slope, ordered from fewest to most trees.
+#[derive(Debug, Eq, PartialEq)]
+struct SlopeReport {
+    counts: Vec<((usize, usize), usize)>,
+}
+
+impl SlopeReport {
+    /// Counts the trees for every rational slope with `dx` and `dy` no greater than `bound`.
+    ///
+    /// Each rational slope is explored once, as `(dx, dy)` in lowest terms.
+    fn explore(data: &[Entry], bound: usize) -> Result<Self> {
+        let mut counts = (0..=bound)
+            .flat_map(|dx| (1..=bound).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| gcd(dx, dy) == 1)
+            .map(|(dx, dy)| Ok(((dx, dy), trees_on_slope(data, dx, dy)?)))
+            .collect::<Result<Vec<_>>>()?;
+        counts.sort_by_key(|&((dx, dy), count)| (count, dy, dx));
+
+        Ok(SlopeReport { counts })
+    }
+
+    /// Returns the slopes tied for the fewest trees.
+    fn best(&self) -> &[((usize, usize), usize)] {
+        let fewest = self.counts.first().map_or(0, |&(_, count)| count);
+        let end = self.counts.partition_point(|&(_, count)| count == fewest);
+        &self.counts[..end]
+    }
+
+    /// Returns the slopes tied for the most trees.
+    fn worst(&self) -> &[((usize, usize), usize)] {
+        let most = self.counts.last().map_or(0, |&(_, count)| count);
+        let start = self.counts.partition_point(|&(_, count)| count < most);
+        &self.counts[start..]
+    }
+}
+
+/// Lists the tree count of every slope, then the slopes tied for the fewest and the most.
+impl fmt::Display for SlopeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &((dx, dy), count) in &self.counts {
+            writeln!(f, "right {} down {}: {} trees", dx, dy, count)?;
+        }
+
+        let slopes = |counts: &[((usize, usize), usize)]| counts.iter()
+            .map(|&((dx, dy), _)| format!("right {} down {}", dx, dy))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "fewest: {}", slopes(self.best()))?;
+        writeln!(f, "most: {}", slopes(self.worst()))
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Renders the map with the path for the slope marked by `O` on open squares and `X` on
+/// trees.
+///
+/// The map is repeated to the right as many times as needed to show the whole path.
+fn render_path(data: &[Entry], dx: usize, dy: usize) -> Result<String> {
+    let visited = path(data, dx, dy)?.collect::<HashSet<_>>();
+    let width = data.first().map_or(0, |entry| entry.map.len());
+    let max_x = visited.iter().map(|&(x, _)| x).max().unwrap_or(0);
+    let repeats = max_x / width.max(1) + 1;
+
+    let rendered = data.iter()
+        .enumerate()
+        .map(|(y, entry)|
+            (0..width * repeats)
+                .map(|x| match (visited.contains(&(x, y)), entry.is_tree(x)) {
+                    (true, true) => 'X',
+                    (true, false) => 'O',
+                    (false, true) => '#',
+                    (false, false) => '.',
+                })
+                .chain(std::iter::once('\n'))
+                .collect::<String>()
         )
-        .fold((0, 0), |(count, x), entry|
-            if entry.map[x % width] {
-                (count + 1, x + dx)
-            } else {
-                (count, x + dx)
-            }
-        );
+        .collect();
+
+    Ok(rendered)
+}
+
+/// Parses the map one row per line, checking that every row has the same width.
+fn parse_map(input: &str) -> Result<Vec<Entry>> {
+    let data = lib::parse_input::<Entry>(input)?;
+    let width = data.first().map_or(0, |entry| entry.map.len());
+    let ragged = data.iter()
+        .enumerate()
+        .find(|(_, entry)| entry.map.len() != width);
+    if let Some((row_idx, entry)) = ragged {
+        return Err(anyhow::Error::new(Error::ParseError).context(
+            format!("Row {} is {} squares wide, expected {}", row_idx + 1, entry.map.len(), width)
+        ));
+    }
 
-    count
+    Ok(data)
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -59,11 +183,27 @@ struct Entry {
     map: Vec<bool>,
 }
 
+impl Entry {
+    fn is_tree(&self, x: usize) -> bool {
+        self.map[x % self.map.len()]
+    }
+}
+
 impl FromStr for Entry {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let map = s.chars().map(|c| c == '#').collect();
+        let map = s.chars()
+            .map(|c| match c {
+                '.' => Ok(false),
+                '#' => Ok(true),
+                _ => Err(Error::ParseError),
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+        if map.is_empty() {
+            return Err(Error::ParseError.into());
+        }
+
         Ok(Entry { map })
     }
 }
@@ -71,7 +211,7 @@ impl FromStr for Entry {
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use super::{lib, Entry};
+    use super::{lib, Entry, SlopeReport};
 
     fn data() -> Vec<Entry> {
         let input = indoc!{"
@@ -87,7 +227,7 @@ mod test {
             #...##....#
             .#..#...#.#
         "};
-        lib::parse_input(input).unwrap()
+        super::parse_map(input).unwrap()
     }
 
     #[test]
@@ -115,4 +255,69 @@ mod test {
     fn part2() {
         assert_eq!(super::part2(&data()).unwrap(), 336);
     }
+
+    #[test]
+    fn explore() {
+        let report = SlopeReport::explore(&data(), 3).unwrap();
+        assert_eq!(report.counts, vec![
+            ((1, 3), 0),
+            ((2, 1), 1),
+            ((2, 3), 1),
+            ((1, 1), 2),
+            ((1, 2), 2),
+            ((3, 2), 2),
+            ((0, 1), 3),
+            ((3, 1), 7),
+        ]);
+        assert_eq!(report.best(), &[((1, 3), 0)]);
+        assert_eq!(report.worst(), &[((3, 1), 7)]);
+
+        let report = SlopeReport::explore(&data()[..1], 2).unwrap();
+        assert_eq!(report.best().len(), 4);
+        assert_eq!(report.worst().len(), 4);
+    }
+
+    #[test]
+    fn report() {
+        let report = SlopeReport::explore(&data()[..3], 1).unwrap();
+        let expected = indoc!{"
+            right 1 down 1: 0 trees
+            right 0 down 1: 1 trees
+            fewest: right 1 down 1
+            most: right 0 down 1
+        "};
+        assert_eq!(report.to_string(), expected);
+    }
+
+    #[test]
+    fn render_path() {
+        let expected = indoc!{"
+            O.##.........##.......
+            #..O#...#..#...#...#..
+            .#....X..#..#....#..#.
+            ..#.#...#O#..#.#...#.#
+            .#...##..#..X...##..#.
+            ..#.##.......#.X#.....
+            .#.#.#....#.#.#.#.O..#
+            .#........#.#........X
+        "};
+
+        assert_eq!(super::render_path(&data()[..8], 3, 1).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(lib::parse_input::<Entry>("..#\n\n#..\n").is_err());
+        assert!(lib::parse_input::<Entry>("..#\n.o.\n").is_err());
+
+        let error = super::parse_map("..#\n#...\n").unwrap_err();
+        assert_eq!(error.to_string(), "Row 2 is 4 squares wide, expected 3");
+    }
+
+    #[test]
+    fn flat_slope() {
+        let error = super::trees_on_slope(&data(), 1, 0).unwrap_err();
+        assert_eq!(error.to_string(), "Slope (1, 0) does not move down");
+        assert!(super::render_path(&data(), 1, 0).is_err());
+    }
 }
@@ -1,105 +1,326 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use thiserror::Error;
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+use crate::lib::{self, Error, Part};
+
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(passports: &[Passport]) -> Result<usize> {
-    let num_valid = passports.iter()
-            .filter(|passport| passport.is_valid())
-            .count();
-    Ok(num_valid)
+    let policy = PRESENCE_POLICY.parse::<Policy>()?;
+    Ok(count_valid(passports, &policy))
 }
 
 fn part2(passports: &[Passport]) -> Result<usize> {
-    let num_valid = passports.iter()
-            .filter(|passport| passport.is_extra_valid())
-            .count();
-    Ok(num_valid)
+    let policy = strict_policy()?;
+    Ok(count_valid(passports, &policy))
+}
+
+/// Runs the `check [POLICY]` command, which lists the violations of every invalid passport
+/// against the policy in the given file, or the part two policy by default.
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    let policy = match args {
+        ["check"] => strict_policy()?,
+        ["check", path] => load_policy(Path::new(path))?,
+        _ => bail!("Usage: check [POLICY]"),
+    };
+    let passports = parse_input(input)?;
+
+    Ok(report(&passports, &policy))
+}
+
+fn count_valid(passports: &[Passport], policy: &Policy) -> usize {
+    passports.iter()
+        .filter(|passport| policy.is_valid(passport))
+        .count()
+}
+
+/// Loads the part two policy from `inputs/day04.policy`, or falls back to [`STRICT_POLICY`]
+/// if there is no such file.
+fn strict_policy() -> Result<Policy> {
+    let path = lib::input_file_path(4).with_extension("policy");
+    if path.exists() {
+        load_policy(&path)
+    } else {
+        STRICT_POLICY.parse()
+    }
+}
+
+fn load_policy(path: &Path) -> Result<Policy> {
+    lib::load_input(path)?
+        .parse()
+        .with_context(|| format!("Invalid policy: '{}'", path.display()))
 }
 
+/// Lists the violations of each invalid passport, numbered from 1 in input order, followed by
+/// the number of valid passports.
+fn report(passports: &[Passport], policy: &Policy) -> String {
+    let mut report = String::new();
+    for (passport_idx, passport) in passports.iter().enumerate() {
+        let violations = policy.check(passport);
+        if !violations.is_empty() {
+            report += &format!("passport {}\n", passport_idx + 1);
+            for violation in violations {
+                report += &format!("    {}\n", violation);
+            }
+        }
+    }
+    report += &format!("{} of {} passports are valid\n", count_valid(passports, policy), passports.len());
+
+    report
+}
+
+/// Requires every field except `cid` to be present.
+const PRESENCE_POLICY: &str = "
+    byr any
+    iyr any
+    eyr any
+    hgt any
+    hcl any
+    ecl any
+    pid any
+    cid? any
+";
+
+/// Requires every field except `cid` to be present and well formed.
+const STRICT_POLICY: &str = "
+    byr range 1920-2002
+    iyr range 2010-2020
+    eyr range 2020-2030
+    hgt units 150-193cm 59-76in
+    hcl regex ^#[0-9a-f]{6}$
+    ecl oneof amb blu brn gry grn hzl oth
+    pid regex ^[0-9]{9}$
+    cid? any
+";
+
 #[derive(Debug, Eq, PartialEq)]
 struct Passport {
     pairs: HashMap<String, String>
 }
 
-impl Passport {
-    fn is_valid(&self) -> bool {
-        let required_keys = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
-        let _optional_keys = ["cid"];
-        required_keys.iter()
-            .map(|&required_key| self.pairs.contains_key(required_key))
-            .all(|contains_key| contains_key == true)
-    }
+/// A set of field rules that passports are checked against.
+///
+/// A policy is written one rule per line as `<field>[?] <constraint> <args>...` where a
+/// trailing `?` marks the field as optional.  Blank lines and lines starting with `#` are
+/// ignored.  The constraints are:
+///
+/// * `any`: no constraint on the value
+/// * `range <min>-<max>`: a number within the inclusive range
+/// * `units <min>-<max><unit>...`: a number followed by one of the units, within the range
+///   for that unit
+/// * `regex <pattern>`: a value matching the pattern
+/// * `oneof <value>...`: one of the listed values
+#[derive(Debug)]
+struct Policy {
+    rules: Vec<FieldRule>,
+}
 
-    fn is_extra_valid(&self) -> bool {
-        if !self.is_valid() { return false; }
+#[derive(Debug)]
+struct FieldRule {
+    field: String,
+    required: bool,
+    constraint: Constraint,
+}
 
-        let byr = self.get_year("byr");
-        if byr < 1920 || byr > 2002 { return false; }
+#[derive(Debug)]
+enum Constraint {
+    Any,
+    Range(RangeInclusive<u32>),
+    Units(Vec<(String, RangeInclusive<u32>)>),
+    Regex(Regex),
+    OneOf(Vec<String>),
+}
 
-        let iyr = self.get_year("iyr");
-        if iyr < 2010 || iyr > 2020 { return false; }
+/// A field that failed a rule.
+#[derive(Debug, Eq, PartialEq)]
+struct Violation {
+    field: String,
+    reason: Reason,
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+enum Reason {
+    #[error("missing")]
+    Missing,
+    #[error("'{0}' is not a number")]
+    NotANumber(String),
+    #[error("{value} is outside {min}-{max}")]
+    OutOfRange { value: u32, min: u32, max: u32 },
+    #[error("'{0}' does not have a known unit")]
+    UnknownUnit(String),
+    #[error("'{value}' does not match '{pattern}'")]
+    NoMatch { value: String, pattern: String },
+    #[error("'{0}' is not an allowed value")]
+    NotAllowed(String),
+}
 
-        let eyr = self.get_year("eyr");
-        if eyr < 2020 || eyr > 2030 { return false; }
+impl Policy {
+    fn is_valid(&self, passport: &Passport) -> bool {
+        self.check(passport).is_empty()
+    }
+
+    /// Returns every rule the passport violates.
+    fn check(&self, passport: &Passport) -> Vec<Violation> {
+        self.rules.iter()
+            .filter_map(|rule| {
+                let reason = match passport.pairs.get(&rule.field) {
+                    Some(value) => rule.constraint.check(value).err(),
+                    None if rule.required => Some(Reason::Missing),
+                    None => None,
+                };
+                reason.map(|reason| Violation { field: rule.field.clone(), reason })
+            })
+            .collect()
+    }
+}
 
-        let hgt = self.pairs.get("hgt").unwrap();
-        let hgt_value = hgt.chars().take_while(|c| match c { '0'..='9' => true, _ => false }).collect::<String>().parse::<u32>().unwrap();
-        if hgt.contains("cm") {
-            if hgt_value < 150 || hgt_value > 193 { return false; }
-        } else if hgt.contains("in") {
-            if hgt_value < 59 || hgt_value > 76 { return false; }
-        } else {
-            return false;
+impl Constraint {
+    fn check(&self, value: &str) -> Result<(), Reason> {
+        match self {
+            Constraint::Any => Ok(()),
+            Constraint::Range(range) => check_range(parse_number(value)?, range),
+            Constraint::Units(units) => {
+                let (number, unit) = value.split_at(
+                    value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len())
+                );
+                let range = units.iter()
+                    .find_map(|(name, range)| if name == unit { Some(range) } else { None })
+                    .ok_or_else(|| Reason::UnknownUnit(value.into()))?;
+                check_range(parse_number(number)?, range)
+            }
+            Constraint::Regex(regex) => {
+                if regex.is_match(value) {
+                    Ok(())
+                } else {
+                    Err(Reason::NoMatch { value: value.into(), pattern: regex.as_str().into() })
+                }
+            }
+            Constraint::OneOf(allowed) => {
+                if allowed.iter().any(|allowed| allowed == value) {
+                    Ok(())
+                } else {
+                    Err(Reason::NotAllowed(value.into()))
+                }
+            }
         }
+    }
+}
 
-        let hcl = self.pairs.get("hcl").unwrap();
-        if hcl.len() != 7 { return false; }
-        let mut hcl_chars = hcl.chars();
-        if hcl_chars.next().unwrap() != '#' { return false; }
-        if !hcl_chars.all(|c| match c { '0'..='9' | 'a'..='f' => true, _ => false }) { return false; }
+fn parse_number(value: &str) -> Result<u32, Reason> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(Reason::NotANumber(value.into()));
+    }
+    value.parse().map_err(|_| Reason::NotANumber(value.into()))
+}
 
-        let valid_ecls = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
-        let ecl = self.pairs.get("ecl").unwrap();
-        if !valid_ecls.iter().any(|valid_ecl| ecl == valid_ecl) { return false; }
+fn check_range(value: u32, range: &RangeInclusive<u32>) -> Result<(), Reason> {
+    if range.contains(&value) {
+        Ok(())
+    } else {
+        Err(Reason::OutOfRange { value, min: *range.start(), max: *range.end() })
+    }
+}
 
-        let pid = self.pairs.get("pid").unwrap();
-        if pid.len() != 9 { return false; }
-        if !pid.chars().all(|c| match c { '0'..='9' => true, _ => false }) { return false; }
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
 
-        true
+impl FromStr for Policy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules = s
+            .lines()
+            .enumerate()
+            .map(|(line_idx, line)| (line_idx, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_idx, line)|
+                FieldRule::from_str(line).with_context(||
+                    format!("Could not parse policy line {}: '{}'", line_idx + 1, line)
+                )
+            )
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Policy { rules })
     }
+}
 
-    fn get_year(&self, key: &str) -> u32 {
-        let year = self.pairs.get(key).unwrap();
-        year.parse::<u32>().unwrap()
+impl FromStr for FieldRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let field = words.next().ok_or(Error::ParseError)?;
+        let (field, required) = match field.strip_suffix('?') {
+            Some(field) => (field, false),
+            None => (field, true),
+        };
+        let kind = words.next().ok_or(Error::ParseError)?;
+        let args = words.collect::<Vec<&str>>();
+
+        let constraint = match (kind, args.as_slice()) {
+            ("any", []) => Constraint::Any,
+            ("range", [range]) => Constraint::Range(parse_range(range)?),
+            ("units", units) if !units.is_empty() => Constraint::Units(
+                units.iter()
+                    .map(|unit| {
+                        let idx = unit.rfind(|c: char| c.is_ascii_digit()).ok_or(Error::ParseError)? + 1;
+                        let (range, name) = unit.split_at(idx);
+                        if name.is_empty() { Err(Error::ParseError)? }
+                        Ok((name.into(), parse_range(range)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            ),
+            ("regex", [pattern]) => Constraint::Regex(Regex::new(pattern)?),
+            ("oneof", values) if !values.is_empty() => Constraint::OneOf(
+                values.iter().map(|&value| value.into()).collect()
+            ),
+            _ => Err(Error::ParseError)?,
+        };
+
+        Ok(FieldRule { field: field.into(), required, constraint })
     }
 }
 
+fn parse_range(s: &str) -> Result<RangeInclusive<u32>> {
+    let (min, max) = s.split_once('-').ok_or(Error::ParseError)?;
+    Ok(min.parse()?..=max.parse()?)
+}
+
 impl FromStr for Passport {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let pairs = s
             .trim_end()
-            .split(|c| c == ' ' || c == '\n');
+            .split([' ', '\n']);
         let pairs = pairs
             .map(|pair| {
-                let pair = pair
-                    .split(":")
-                    .collect::<Vec<&str>>();
+                let (key, value) = pair
+                    .split_once(':')
+                    .ok_or(Error::ParseError)
+                    .with_context(|| format!("Missing ':' in passport field '{}'", pair))?;
 
-                (pair[0].into(), pair[1].into())
+                Ok((key.into(), value.into()))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<Result<HashMap<_, _>>>()?;
 
         Ok(Passport { pairs })
     }
@@ -117,10 +338,10 @@ fn parse_input(input: &str) -> Result<Vec<Passport>> {
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use super::Passport;
+    use super::{Passport, Policy, Reason, Violation};
 
     fn input() -> &'static str {
-        let input = indoc!{"
+        indoc!{"
             ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
             byr:1937 iyr:2017 cid:147 hgt:183cm
 
@@ -134,9 +355,7 @@ mod test {
 
             hcl:#cfa07d eyr:2025 pid:166559648
             iyr:2011 ecl:brn hgt:59in
-        "};
-
-        input
+        "}
     }
 
     fn passports() -> Vec<Passport> {
@@ -188,4 +407,70 @@ mod test {
         let input = super::parse_input(input).unwrap();
         assert_eq!(super::part2(&input).unwrap(), 4);
     }
+
+    #[test]
+    fn violations() {
+        let input = indoc!{"
+            eyr:1972 cid:100
+            hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+        "};
+        let passports = super::parse_input(input).unwrap();
+        let policy = super::STRICT_POLICY.parse::<Policy>().unwrap();
+
+        let actual = policy.check(&passports[0]);
+        let expected = vec![
+            Violation { field: "eyr".into(), reason: Reason::OutOfRange { value: 1972, min: 2020, max: 2030 } },
+            Violation { field: "hgt".into(), reason: Reason::UnknownUnit("170".into()) },
+            Violation { field: "pid".into(), reason: Reason::NoMatch { value: "186cm".into(), pattern: "^[0-9]{9}$".into() } },
+        ];
+        assert_eq!(actual, expected);
+        assert_eq!(actual[0].to_string(), "eyr: 1972 is outside 2020-2030");
+    }
+
+    #[test]
+    fn report() {
+        let policy = super::PRESENCE_POLICY.parse::<Policy>().unwrap();
+        let expected = indoc!{"
+            passport 2
+                hgt: missing
+            passport 4
+                byr: missing
+            2 of 4 passports are valid
+        "};
+        assert_eq!(super::report(&passports(), &policy), expected);
+    }
+
+    #[test]
+    fn custom_policy() {
+        let policy = indoc!{"
+            # Only brown or green eyes, and the country is mandatory
+            ecl oneof brn grn
+            cid range 1-999
+        "};
+        let policy = policy.parse::<Policy>().unwrap();
+
+        let actual = policy.check(&passports()[3]);
+        let expected = vec![
+            Violation { field: "cid".into(), reason: Reason::Missing },
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn policy_parse_error() {
+        let error = "byr range 1920\n".parse::<Policy>().unwrap_err();
+        assert_eq!(error.to_string(), "Could not parse policy line 1: 'byr range 1920'");
+
+        assert!("hgt units 150-193\n".parse::<Policy>().is_err());
+        assert!("hcl regex [\n".parse::<Policy>().is_err());
+    }
+
+    #[test]
+    fn passport_parse_error() {
+        let error = "byr1937 iyr:2017".parse::<Passport>().unwrap_err();
+        assert_eq!(error.to_string(), "Missing ':' in passport field 'byr1937'");
+
+        let error = super::parse_input("ecl:gry byr1937\n\nbyr:1937").unwrap_err();
+        assert_eq!(error.to_string(), "Missing ':' in passport field 'byr1937'");
+    }
 }
@@ -1,48 +1,90 @@
+use std::collections::{BTreeSet, HashSet};
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use thiserror::Error;
 
-use crate::lib;
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(seats: &[Seat]) -> Result<u32> {
-    Ok(seats.iter().map(|seat| seat.id).max().unwrap())
+    let id = seats.iter()
+        .map(|seat| seat.id)
+        .max()
+        .ok_or(Error::NoSolution)?;
+
+    Ok(id)
 }
 
 fn part2(seats: &[Seat]) -> Result<u32> {
-    let mut seats = seats.iter()
-        .filter_map(|seat|
-            if seat.row > 0 && seat.row < 127 {
-                Some(seat.id)
-            } else {
-                None
-            }
-        )
-        .collect::<Vec<u32>>();
-
-    seats.sort();
-
-    let id = seats
-        .chunks(2)
-        .find_map(|pair|
-            if pair[1] - pair[0] == 2 {
-                Some(pair[0] + 1)
-            } else {
-                None
-            }
-        )
-        .unwrap();
+    let id = gaps(seats)
+        .into_iter()
+        .next()
+        .ok_or(Error::NoSolution)?;
 
     Ok(id)
 }
 
+/// Runs one of the seat commands:
+///
+/// * `map`: renders the seat map of the boarding passes
+/// * `encode <row> <col> [<row_bits> <col_bits>]`: encodes a seat as a boarding pass code
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    match args {
+        ["map"] => {
+            let seats = lib::parse_input(input)?;
+            Ok(Layout::DEFAULT.render(&seats))
+        }
+        ["encode", row, col] => encode(Layout::DEFAULT, row, col),
+        ["encode", row, col, row_bits, col_bits] => {
+            let layout = Layout::new(parse_arg(row_bits)?, parse_arg(col_bits)?)?;
+            encode(layout, row, col)
+        }
+        _ => bail!("Usage: map | encode ROW COL [ROW_BITS COL_BITS]"),
+    }
+}
+
+fn encode(layout: Layout, row: &str, col: &str) -> Result<String> {
+    let (row, col) = (parse_arg(row)?, parse_arg(col)?);
+    if row >= layout.rows() || col >= layout.cols() {
+        bail!("Seat ({}, {}) is outside the {}x{} layout", row, col, layout.rows(), layout.cols());
+    }
+
+    Ok(layout.encode(&layout.seat(row, col)) + "\n")
+}
+
+fn parse_arg(arg: &str) -> Result<u32> {
+    arg.parse().with_context(|| format!("Invalid number: '{}'", arg))
+}
+
+/// Returns the IDs of the empty seats whose neighbouring IDs are both occupied.
+fn gaps(seats: &[Seat]) -> Vec<u32> {
+    let ids = seats.iter()
+        .map(|seat| seat.id)
+        .collect::<BTreeSet<u32>>();
+
+    ids.iter()
+        .zip(ids.iter().skip(1))
+        .filter(|(&lo, &hi)| hi - lo == 2)
+        .map(|(&lo, _)| lo + 1)
+        .collect()
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+enum LayoutError {
+    #[error("A layout of {0} bits is wider than the {} bits a seat ID can hold", Layout::MAX_BITS)]
+    TooWide(u64),
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct Seat {
     row: u32,
@@ -50,41 +92,120 @@ struct Seat {
     id: u32,
 }
 
-impl FromStr for Seat {
-    type Err = anyhow::Error;
+/// The number of bits used to encode the row and column of a seat.
+///
+/// A seat code is `row_bits` characters of `F` (lower half) or `B` (upper half) followed by
+/// `col_bits` characters of `L` (lower half) or `R` (upper half).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Layout {
+    row_bits: u32,
+    col_bits: u32,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut row = 0;
-        let mut row_delta = 64;
-        let mut col = 0;
-        let mut col_delta = 4;
-
-        for c in s.chars() {
-            match c {
-                'F' => row_delta /= 2,
-                'B' => {
-                    row += row_delta;
-                    row_delta /= 2;
-                }
-                'L' => col_delta /= 2,
-                'R' => {
-                    col += col_delta;
-                    col_delta /= 2;
-                }
-                _ => panic!(),
-            }
+impl Layout {
+    const DEFAULT: Layout = Layout { row_bits: 7, col_bits: 3 };
+
+    /// Seat IDs have to fit in a `u32`, so the two widths together can be at most 31 bits.
+    const MAX_BITS: u32 = 31;
+
+    fn new(row_bits: u32, col_bits: u32) -> Result<Layout, LayoutError> {
+        let bits = row_bits as u64 + col_bits as u64;
+        if bits > Self::MAX_BITS as u64 {
+            return Err(LayoutError::TooWide(bits));
+        }
+
+        Ok(Layout { row_bits, col_bits })
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    fn cols(&self) -> u32 {
+        1 << self.col_bits
+    }
+
+    fn seat(&self, row: u32, col: u32) -> Seat {
+        Seat { row, col, id: row * self.cols() + col }
+    }
+
+    fn decode(&self, code: &str) -> Result<Seat, Error> {
+        // Checking for ASCII first makes the length in bytes a length in characters, so the
+        // split below always falls on a character boundary.
+        if !code.is_ascii() || code.len() != (self.row_bits + self.col_bits) as usize {
+            return Err(Error::ParseError);
+        }
+
+        let (row_code, col_code) = code.split_at(self.row_bits as usize);
+        let row = decode_bits(row_code, 'F', 'B')?;
+        let col = decode_bits(col_code, 'L', 'R')?;
+
+        Ok(self.seat(row, col))
+    }
+
+    fn encode(&self, seat: &Seat) -> String {
+        encode_bits(seat.row, self.row_bits, 'F', 'B') + &encode_bits(seat.col, self.col_bits, 'L', 'R')
+    }
+
+    /// Renders one line per row with `#` for occupied seats, `.` for empty seats, and `?` for
+    /// gaps.
+    fn render(&self, seats: &[Seat]) -> String {
+        let occupied = seats.iter()
+            .map(|seat| seat.id)
+            .collect::<HashSet<u32>>();
+        let gaps = gaps(seats)
+            .into_iter()
+            .collect::<HashSet<u32>>();
+
+        (0..self.rows())
+            .map(|row|
+                (0..self.cols())
+                    .map(|col| {
+                        let id = self.seat(row, col).id;
+                        if occupied.contains(&id) {
+                            '#'
+                        } else if gaps.contains(&id) {
+                            '?'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            )
+            .collect()
+    }
+}
+
+fn decode_bits(code: &str, zero: char, one: char) -> Result<u32, Error> {
+    code.chars().try_fold(0, |value, c|
+        match c {
+            c if c == zero => Ok(value << 1),
+            c if c == one => Ok(value << 1 | 1),
+            _ => Err(Error::ParseError),
         }
+    )
+}
 
-        let id = row * 8 + col;
+fn encode_bits(value: u32, bits: u32, zero: char, one: char) -> String {
+    (0..bits)
+        .rev()
+        .map(|bit| if value >> bit & 1 == 1 { one } else { zero })
+        .collect()
+}
+
+impl FromStr for Seat {
+    type Err = Error;
 
-        Ok(Seat { row, col, id })
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layout::DEFAULT.decode(s)
     }
 }
 
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use super::{lib, Seat};
+    use super::{lib, Layout, LayoutError, Seat};
 
     fn seats() -> Vec<Seat> {
         let input = indoc!{"
@@ -120,4 +241,92 @@ mod test {
     fn part1() {
         assert_eq!(super::part1(&seats()).unwrap(), 820);
     }
+
+    #[test]
+    fn part2() {
+        let seats = ["FFFFFFFLLL", "FFFFFFFLLR", "FFFFFFFLRR", "FFFFFFFRRR"]
+            .iter()
+            .map(|code| code.parse::<Seat>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(super::part2(&seats).unwrap(), 2);
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!("FBFBBFFRL".parse::<Seat>().is_err());
+        assert!("FBFBBFFRLF".parse::<Seat>().is_err());
+        assert!("FBFBBFRRLR".parse::<Seat>().is_err());
+        assert!("FBFBBFéLR".parse::<Seat>().is_err());
+        assert!("FBFBBFFRLé".parse::<Seat>().is_err());
+    }
+
+    #[test]
+    fn layout_widths() {
+        assert_eq!(Layout::new(32, 0), Err(LayoutError::TooWide(32)));
+        assert_eq!(Layout::new(u32::MAX, 1), Err(LayoutError::TooWide(1 << 32)));
+        assert_eq!(
+            Layout::new(16, 16).unwrap_err().to_string(),
+            "A layout of 32 bits is wider than the 31 bits a seat ID can hold"
+        );
+
+        let layout = Layout::new(28, 3).unwrap();
+        assert_eq!(layout.rows(), 1 << 28);
+        let seat = layout.seat(layout.rows() - 1, layout.cols() - 1);
+        assert_eq!(seat.id, u32::MAX >> 1);
+        assert_eq!(layout.decode(&layout.encode(&seat)).unwrap(), seat);
+    }
+
+    #[test]
+    fn encode() {
+        for seat in seats() {
+            let code = Layout::DEFAULT.encode(&seat);
+            assert_eq!(code.parse::<Seat>().unwrap(), seat);
+        }
+
+        assert_eq!(Layout::DEFAULT.encode(&Seat { row: 44, col: 5, id: 357 }), "FBFBBFFRLR");
+    }
+
+    #[test]
+    fn round_trip_all_layouts() {
+        for row_bits in 0..=8 {
+            for col_bits in 0..=4 {
+                let layout = Layout::new(row_bits, col_bits).unwrap();
+                for row in 0..layout.rows() {
+                    for col in 0..layout.cols() {
+                        let seat = layout.seat(row, col);
+                        let code = layout.encode(&seat);
+                        assert_eq!(layout.decode(&code).unwrap(), seat);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tool() {
+        assert_eq!(super::tool("", &["encode", "44", "5"]).unwrap(), "FBFBBFFRLR\n");
+        assert_eq!(super::tool("", &["encode", "3", "0", "2", "1"]).unwrap(), "BBL\n");
+
+        let error = super::tool("", &["encode", "4", "0", "2", "1"]).unwrap_err();
+        assert_eq!(error.to_string(), "Seat (4, 0) is outside the 4x2 layout");
+        let error = super::tool("", &["encode", "0", "0", "30", "2"]).unwrap_err();
+        assert_eq!(error.to_string(), "A layout of 32 bits is wider than the 31 bits a seat ID can hold");
+    }
+
+    #[test]
+    fn render() {
+        let layout = Layout::new(2, 2).unwrap();
+        let seats = ["FFLR", "FFRL", "FBLL", "FBLR", "FBRR", "BFLL", "BBRR"]
+            .iter()
+            .map(|code| layout.decode(code).unwrap())
+            .collect::<Vec<_>>();
+
+        let expected = indoc!{"
+            .##?
+            ##?#
+            #...
+            ...#
+        "};
+        assert_eq!(layout.render(&seats), expected);
+    }
 }
@@ -1,61 +1,209 @@
-use std::collections::HashSet;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor};
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
-    println!("day{:02}::part1: {}", day, part1(input)?);
-    println!("day{:02}::part2: {}", day, part2(input)?);
+use crate::lib::Part;
 
-    Ok(())
+pub fn run(input: &str, part: Part) -> Result<String> {
+    let groups = parse_input(input)?;
+    let answer = match part {
+        Part::One => part1(&groups)?.to_string(),
+        Part::Two => part2(&groups)?.to_string(),
+    };
+
+    Ok(answer)
+}
+
+fn part1(groups: &[Group]) -> Result<u32> {
+    Ok(groups.iter().map(|group| group.union().len()).sum())
+}
+
+fn part2(groups: &[Group]) -> Result<u32> {
+    Ok(groups.iter().map(|group| group.intersection().len()).sum())
+}
+
+/// Runs one of the group commands:
+///
+/// * `groups`: describes the answers of each group
+/// * `at-least <k>`: sums the number of questions answered by at least `k` people in each
+///   group
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    let groups = parse_input(input)?;
+    match args {
+        ["groups"] => Ok(groups.iter()
+            .enumerate()
+            .map(|(group_idx, group)| format!("group {}: {}\n", group_idx + 1, group.describe()))
+            .collect()),
+        ["at-least", k] => {
+            let k = k.parse::<usize>().with_context(|| format!("Invalid k: '{}'", k))?;
+            let sum = groups.iter().map(|group| group.at_least(k).len()).sum::<u32>();
+            Ok(format!("{}\n", sum))
+        }
+        _ => bail!("Usage: groups | at-least K"),
+    }
+}
+
+/// The questions `a` to `z` answered "yes", one bit per question.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+struct Answers(u32);
+
+impl Answers {
+    const ALL: Answers = Answers((1 << 26) - 1);
+
+    fn len(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    fn contains(self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 & 1 << (question as u8 - b'a') != 0
+    }
+
+    fn questions(self) -> impl Iterator<Item = char> {
+        ('a'..='z').filter(move |&question| self.contains(question))
+    }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let count = input
-        .split("\n\n")
-        .map(|group| {
-            let mut set = HashSet::new();
-            for c in group.chars() {
-                if !c.is_whitespace() {
-                    set.insert(c);
-                }
+impl BitOr for Answers {
+    type Output = Answers;
+
+    fn bitor(self, rhs: Answers) -> Answers {
+        Answers(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Answers;
+
+    fn bitand(self, rhs: Answers) -> Answers {
+        Answers(self.0 & rhs.0)
+    }
+}
+
+impl BitXor for Answers {
+    type Output = Answers;
+
+    fn bitxor(self, rhs: Answers) -> Answers {
+        Answers(self.0 ^ rhs.0)
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.questions().collect::<String>())
+    }
+}
+
+impl FromStr for Answers {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().enumerate().try_fold(Answers::default(), |answers, (idx, c)| {
+            if !c.is_ascii_lowercase() {
+                bail!("Invalid question '{}' at column {}", c, idx + 1);
             }
-            set.len()
+            Ok(answers | Answers(1 << (c as u8 - b'a')))
         })
-        .sum();
-    Ok(count)
+    }
 }
 
-fn part2(input: &str) -> Result<u32> {
-    let count = input
-        .split("\n\n")
-        .map(|group|
-             group
-                .lines()
-                .map(|person| {
-                    let mut answers = 0u32;
-                    for c in person.chars() {
-                        let index = (c as u8) - ('a' as u8);
-                        answers |= 1 << index;
-                    }
-                    answers
-                 })
-                 .fold(0xffffffff, |group_answers, person_answers|
-                    group_answers & person_answers
-                 )
-                 .count_ones()
-        )
-        .sum();
+/// The answers of each person in a group.
+#[derive(Debug, Eq, PartialEq)]
+struct Group {
+    people: Vec<Answers>,
+}
+
+impl Group {
+    /// Questions anyone answered.
+    fn union(&self) -> Answers {
+        self.people.iter().fold(Answers::default(), |acc, &answers| acc | answers)
+    }
+
+    /// Questions everyone answered.
+    fn intersection(&self) -> Answers {
+        self.people.iter().fold(Answers::ALL, |acc, &answers| acc & answers)
+    }
+
+    /// Questions answered by an odd number of people.
+    fn symmetric_difference(&self) -> Answers {
+        self.people.iter().fold(Answers::default(), |acc, &answers| acc ^ answers)
+    }
+
+    /// Questions answered by at least `k` people.
+    fn at_least(&self, k: usize) -> Answers {
+        self.frequencies()
+            .into_iter()
+            .filter(|&(_, count)| count >= k)
+            .fold(Answers::default(), |acc, (question, _)| acc | Answers(1 << (question as u8 - b'a')))
+    }
 
-    Ok(count)
+    /// Returns the number of people who answered each question, for the questions anyone
+    /// answered.
+    fn frequencies(&self) -> Vec<(char, usize)> {
+        self.union()
+            .questions()
+            .map(|question| {
+                let count = self.people.iter()
+                    .filter(|answers| answers.contains(question))
+                    .count();
+                (question, count)
+            })
+            .collect()
+    }
+
+    /// Summarises the group's answers as its union, intersection, symmetric difference and
+    /// frequencies, with `-` for an empty set.
+    fn describe(&self) -> String {
+        let set = |answers: Answers| if answers.len() == 0 { "-".into() } else { answers.to_string() };
+        let frequencies = self.frequencies()
+            .into_iter()
+            .map(|(question, count)| format!("{}={}", question, count))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "anyone {}, everyone {}, odd {}, counts {}",
+            set(self.union()),
+            set(self.intersection()),
+            set(self.symmetric_difference()),
+            frequencies,
+        )
+    }
 }
 
+/// Parses groups of lines separated by blank lines.
+fn parse_input(input: &str) -> Result<Vec<Group>> {
+    let mut groups = Vec::new();
+    let mut people = Vec::new();
+
+    for (line_idx, line) in input.lines().enumerate() {
+        if line.is_empty() {
+            if !people.is_empty() {
+                groups.push(Group { people: std::mem::take(&mut people) });
+            }
+            continue;
+        }
+
+        let answers = line.parse::<Answers>().with_context(||
+            format!("Could not parse line {}: '{}'", line_idx + 1, line)
+        )?;
+        people.push(answers);
+    }
+
+    if !people.is_empty() {
+        groups.push(Group { people });
+    }
+
+    Ok(groups)
+}
 
 #[cfg(test)]
 mod test {
     use indoc::indoc;
+    use super::Group;
 
     fn input() -> &'static str {
-        let input = indoc!{"
+        indoc!{"
             abc
 
             a
@@ -71,18 +219,70 @@ mod test {
             a
 
             b
-        "};
+        "}
+    }
 
-        input
+    fn groups() -> Vec<Group> {
+        super::parse_input(input()).unwrap()
     }
 
     #[test]
     fn part1() {
-        assert_eq!(super::part1(input()).unwrap(), 11);
+        assert_eq!(super::part1(&groups()).unwrap(), 11);
     }
 
     #[test]
     fn part2() {
-        assert_eq!(super::part2(input()).unwrap(), 6);
+        assert_eq!(super::part2(&groups()).unwrap(), 6);
+    }
+
+    #[test]
+    fn parse() {
+        let groups = groups();
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[1].people.len(), 3);
+        assert_eq!(groups[1].people[2].to_string(), "c");
+    }
+
+    #[test]
+    fn parse_error() {
+        let input = indoc!{"
+            abc
+
+            ab
+            aC
+        "};
+        let error = super::parse_input(input).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Could not parse line 4: 'aC': Invalid question 'C' at column 2",
+        );
+    }
+
+    #[test]
+    fn set_algebra() {
+        let group = Group { people: ["abcx", "abcy", "abcz", "ax"].iter().map(|s| s.parse().unwrap()).collect() };
+
+        assert_eq!(group.union().to_string(), "abcxyz");
+        assert_eq!(group.intersection().to_string(), "a");
+        assert_eq!(group.symmetric_difference().to_string(), "bcyz");
+        assert_eq!(group.at_least(2).to_string(), "abcx");
+        assert_eq!(group.at_least(4), group.intersection());
+        assert_eq!(group.at_least(1), group.union());
+        assert_eq!(group.frequencies(), vec![('a', 4), ('b', 3), ('c', 3), ('x', 2), ('y', 1), ('z', 1)]);
+        assert_eq!(
+            group.describe(),
+            "anyone abcxyz, everyone a, odd bcyz, counts a=4 b=3 c=3 x=2 y=1 z=1",
+        );
+    }
+
+    #[test]
+    fn tool() {
+        assert_eq!(super::tool(input(), &["at-least", "1"]).unwrap(), "11\n");
+        assert_eq!(super::tool(input(), &["at-least", "2"]).unwrap(), "2\n");
+        assert!(super::tool(input(), &["groups"]).unwrap().starts_with("group 1: anyone abc,"));
+
+        let group = Group { people: vec!["ab".parse().unwrap(), "ab".parse().unwrap()] };
+        assert_eq!(group.describe(), "anyone ab, everyone ab, odd -, counts a=2 b=2");
     }
 }
@@ -1,63 +1,202 @@
+use std::cell::OnceCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
-use std::collections::{HashMap, HashSet};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use thiserror::Error;
 
-use crate::lib;
+use crate::lib::{self, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(rules: &[Rule]) -> Result<usize> {
-    let mut map: HashMap<&str, Vec<&str>> = HashMap::new();
+    let graph = BagGraph::new(rules)?;
+    Ok(graph.containers_of("shiny gold")?.len())
+}
 
-    for rule in rules {
-        for item in rule.content.iter() {
-            let entry = map.entry(&item.1).or_insert_with(|| Vec::new());
-            entry.push(&rule.desc);
-        }
+fn part2(rules: &[Rule]) -> Result<u64> {
+    let graph = BagGraph::new(rules)?;
+    Ok(graph.bags_inside("shiny gold")?)
+}
+
+/// Runs the `dot` command, which renders the bag graph in Graphviz DOT format.
+pub fn tool(input: &str, args: &[&str]) -> Result<String> {
+    if args != ["dot"] {
+        bail!("Usage: dot");
     }
+    let rules = lib::parse_input::<Rule>(input)?;
 
-    let mut containing = HashSet::new();
-    gather_containing("shiny gold", &map, &mut containing);
+    Ok(BagGraph::new(&rules)?.to_dot())
+}
 
-    Ok(containing.len())
+#[derive(Error, Debug, Eq, PartialEq)]
+enum GraphError {
+    #[error("No bag named '{0}'")]
+    UnknownBag(String),
+    #[error("More than one rule for '{0}' bags")]
+    DuplicateRule(String),
+    #[error("Bags contain themselves: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
 }
 
-fn gather_containing<'a>(key: &'a str, map: &HashMap<&str, Vec<&'a str>>, containing: &mut HashSet<&'a str>) {
-    if map.contains_key(key) {
-        for desc in map.get(key).unwrap() {
-            gather_containing(desc, map, containing);
-            containing.insert(desc);
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct BagId(usize);
+
+/// Weighted digraph of bag containment.
+///
+/// An edge `a -> b` with weight `n` means an `a` bag directly contains `n` `b` bags.  The
+/// graph is checked to be acyclic on construction so that the transitive queries terminate.
+/// Their results are memoised per bag.
+#[derive(Debug)]
+struct BagGraph<'a> {
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, BagId>,
+    contents: Vec<Vec<(BagId, u32)>>,
+    containers: Vec<Vec<BagId>>,
+    all_containers: Vec<OnceCell<BTreeSet<BagId>>>,
+    bags_inside: Vec<OnceCell<u64>>,
+}
+
+impl<'a> BagGraph<'a> {
+    fn new(rules: &'a [Rule]) -> Result<Self, GraphError> {
+        let mut graph = BagGraph {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            contents: Vec::new(),
+            containers: Vec::new(),
+            all_containers: Vec::new(),
+            bags_inside: Vec::new(),
+        };
+
+        let mut described = HashSet::new();
+        for rule in rules {
+            if !described.insert(rule.desc.as_str()) {
+                return Err(GraphError::DuplicateRule(rule.desc.clone()));
+            }
+            let id = graph.insert(&rule.desc);
+            for (qty, desc) in rule.content.iter() {
+                let inner = graph.insert(desc);
+                graph.contents[id.0].push((inner, *qty));
+                graph.containers[inner.0].push(id);
+            }
         }
+
+        graph.check_acyclic()?;
+
+        Ok(graph)
     }
-}
 
-fn part2(rules: &[Rule]) -> Result<u32> {
-    let mut map: HashMap<&str, Vec<&(u32, String)>> = HashMap::new();
+    fn insert(&mut self, name: &'a str) -> BagId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = BagId(self.names.len());
+        self.names.push(name);
+        self.ids.insert(name, id);
+        self.contents.push(Vec::new());
+        self.containers.push(Vec::new());
+        self.all_containers.push(OnceCell::new());
+        self.bags_inside.push(OnceCell::new());
+        id
+    }
 
-    for rule in rules {
-        let entry = map.entry(&rule.desc).or_insert_with(|| Vec::new());
-        for item in rule.content.iter() {
-            entry.push(item);
+    fn id(&self, name: &str) -> Result<BagId, GraphError> {
+        self.ids.get(name).copied().ok_or_else(|| GraphError::UnknownBag(name.into()))
+    }
+
+    fn check_acyclic(&self) -> Result<(), GraphError> {
+        #[derive(Copy, Clone, Eq, PartialEq)]
+        enum State { Unvisited, OnPath, Done }
+
+        fn visit(graph: &BagGraph, id: BagId, states: &mut [State], path: &mut Vec<BagId>) -> Result<(), GraphError> {
+            match states[id.0] {
+                State::Done => return Ok(()),
+                State::OnPath => {
+                    let start = path.iter().position(|&on_path| on_path == id).unwrap();
+                    let cycle = path[start..].iter()
+                        .chain(std::iter::once(&id))
+                        .map(|&id| graph.names[id.0].to_string())
+                        .collect();
+                    return Err(GraphError::Cycle(cycle));
+                }
+                State::Unvisited => (),
+            }
+
+            states[id.0] = State::OnPath;
+            path.push(id);
+            for &(inner, _) in graph.contents[id.0].iter() {
+                visit(graph, inner, states, path)?;
+            }
+            path.pop();
+            states[id.0] = State::Done;
+
+            Ok(())
         }
+
+        let mut states = vec![State::Unvisited; self.names.len()];
+        let mut path = Vec::new();
+        (0..self.names.len())
+            .try_for_each(|idx| visit(self, BagId(idx), &mut states, &mut path))
     }
 
-    let count = count_bags("shiny gold", &map);
-    Ok(count)
-}
+    /// Returns the names of all bags that eventually contain a `name` bag.
+    fn containers_of(&self, name: &str) -> Result<Vec<&'a str>, GraphError> {
+        let id = self.id(name)?;
+        Ok(
+            self.all_containers(id).iter()
+                .map(|id| self.names[id.0])
+                .collect()
+        )
+    }
 
-fn count_bags(key: &str, map: &HashMap<&str, Vec<&(u32, String)>>) -> u32 {
-    map.get(key)
-        .unwrap()
-        .iter()
-        .map(|(qty, desc)| qty + qty * count_bags(desc, map))
-        .sum::<u32>()
+    fn all_containers(&self, id: BagId) -> &BTreeSet<BagId> {
+        self.all_containers[id.0].get_or_init(||
+            self.containers[id.0].iter()
+                .flat_map(|&container|
+                    std::iter::once(container)
+                        .chain(self.all_containers(container).iter().copied())
+                )
+                .collect()
+        )
+    }
+
+    /// Returns the total number of bags inside a `name` bag.
+    fn bags_inside(&self, name: &str) -> Result<u64, GraphError> {
+        let id = self.id(name)?;
+        Ok(self.bags_inside_id(id))
+    }
+
+    fn bags_inside_id(&self, id: BagId) -> u64 {
+        *self.bags_inside[id.0].get_or_init(||
+            self.contents[id.0].iter()
+                .map(|&(inner, qty)| qty as u64 * (1 + self.bags_inside_id(inner)))
+                .sum()
+        )
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bags {\n");
+        for (idx, contents) in self.contents.iter().enumerate() {
+            if contents.is_empty() && self.containers[idx].is_empty() {
+                dot += &format!("    \"{}\";\n", self.names[idx]);
+            }
+            for (inner, qty) in contents {
+                dot += &format!("    \"{}\" -> \"{}\" [label={}];\n", self.names[idx], self.names[inner.0], qty);
+            }
+        }
+        dot += "}\n";
+        dot
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -76,19 +215,15 @@ impl FromStr for Rule {
         let mut content = Vec::new();
         iter.next();
         iter.next();
-        loop {
-            if let Some(qty) = iter.next() {
-                let qty = if let Ok(qty) = qty.parse::<u32>() {
-                    qty
-                } else {
-                    break
-                };
-                let desc = iter.by_ref().take(2).collect::<Vec<&str>>().join(" ");
-                content.push((qty, desc));
-                iter.next();
+        while let Some(qty) = iter.next() {
+            let qty = if let Ok(qty) = qty.parse::<u32>() {
+                qty
             } else {
-                break;
-            }
+                break
+            };
+            let desc = iter.by_ref().take(2).collect::<Vec<&str>>().join(" ");
+            content.push((qty, desc));
+            iter.next();
         }
 
         Ok(Rule { desc, content })
@@ -98,7 +233,7 @@ impl FromStr for Rule {
 #[cfg(test)]
 mod test {
     use indoc::indoc;
-    use super::{lib, Rule};
+    use super::{lib, BagGraph, GraphError, Rule};
 
     #[test]
     fn parse() {
@@ -161,4 +296,77 @@ mod test {
 
         assert_eq!(super::part2(&data).unwrap(), 126);
     }
+
+    #[test]
+    fn containers_of() {
+        let data = data();
+        let graph = BagGraph::new(&data).unwrap();
+
+        let mut actual = graph.containers_of("muted yellow").unwrap();
+        actual.sort_unstable();
+        assert_eq!(actual, vec!["dark orange", "light red"]);
+
+        assert_eq!(graph.containers_of("mauve").unwrap_err(), GraphError::UnknownBag("mauve".into()));
+    }
+
+    #[test]
+    fn bags_inside() {
+        let data = data();
+        let graph = BagGraph::new(&data).unwrap();
+
+        assert_eq!(graph.bags_inside("dark olive").unwrap(), 7);
+        assert_eq!(graph.bags_inside("faded blue").unwrap(), 0);
+    }
+
+    #[test]
+    fn cycle() {
+        let input = indoc!{"
+            light red bags contain 1 bright white bag.
+            bright white bags contain 2 muted yellow bags, 1 faded blue bag.
+            muted yellow bags contain 1 light red bag.
+            faded blue bags contain no other bags.
+        "};
+        let data: Vec<Rule> = lib::parse_input(input).unwrap();
+
+        let error = BagGraph::new(&data).unwrap_err();
+        assert_eq!(error, GraphError::Cycle(vec![
+            "light red".into(),
+            "bright white".into(),
+            "muted yellow".into(),
+            "light red".into(),
+        ]));
+        assert_eq!(error.to_string(), "Bags contain themselves: light red -> bright white -> muted yellow -> light red");
+    }
+
+    #[test]
+    fn duplicate_rule() {
+        let input = indoc!{"
+            faded blue bags contain no other bags.
+            faded blue bags contain 1 light red bag.
+        "};
+        let data: Vec<Rule> = lib::parse_input(input).unwrap();
+
+        assert_eq!(BagGraph::new(&data).unwrap_err(), GraphError::DuplicateRule("faded blue".into()));
+    }
+
+    #[test]
+    fn to_dot() {
+        let input = indoc!{"
+            bright white bags contain 1 shiny gold bag.
+            shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+            mirrored tan bags contain no other bags.
+        "};
+        let data: Vec<Rule> = lib::parse_input(input).unwrap();
+        let graph = BagGraph::new(&data).unwrap();
+
+        let expected = indoc!{r#"
+            digraph bags {
+                "bright white" -> "shiny gold" [label=1];
+                "shiny gold" -> "dark olive" [label=1];
+                "shiny gold" -> "vibrant plum" [label=2];
+                "mirrored tan";
+            }
+        "#};
+        assert_eq!(graph.to_dot(), expected);
+    }
 }
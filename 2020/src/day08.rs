@@ -3,14 +3,16 @@ use std::str::FromStr;
 
 use anyhow::Result;
 
-use crate::lib::{self, Error};
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(instrs: &[Instr]) -> Result<i32> {
@@ -43,7 +45,7 @@ fn execute(instrs: &[Instr]) -> Execution {
                             }
                             Instr::Jmp(arg) => {
                                 if arg.is_negative() {
-                                    ip = match ip.checked_sub(arg.abs() as usize) {
+                                    ip = match ip.checked_sub(arg.unsigned_abs() as usize) {
                                         Some(ip) => ip,
                                         None => return Execution::OutOfBounds,
                                     }
@@ -75,7 +77,7 @@ fn part2(instrs: &[Instr]) -> Result<i32> {
         let index = start_index + relative_index;
         start_index = index + 1;
 
-        let instr_copy = *instr.clone();
+        let instr_copy = **instr;
         *instrs.get_mut(index).unwrap() = swap(instr);
 
         match execute(&instrs) {
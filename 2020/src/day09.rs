@@ -1,46 +1,120 @@
+use std::collections::{HashMap, VecDeque};
+
 use anyhow::Result;
-use itertools::Itertools;
 
-use crate::lib::{self, Error};
+use crate::lib::{self, Error, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data, 25)?);
-    println!("day{:02}::part2: {}", day, part2(&data, 25)?);
+    let answer = match part {
+        Part::One => part1(&data, 25)?.to_string(),
+        Part::Two => part2(&data, 25)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(values: &[u64], window_size: usize) -> Result<u64> {
-    let bad_value = values.windows(window_size + 1)
-        .find(|window| {
-            let (sub_window, value) = window.split_at(window_size);
-            let value = value[0];
-
-            !sub_window.iter()
-                .combinations(2)
-                .map(|comb| comb.into_iter().sum::<u64>())
-                .any(|sum| sum == value)
-        })
-        .ok_or(Error::NoSolution)?
-        .get(window_size)
-        .unwrap();
-
-    Ok(*bad_value)
+    let (_, bad_value) = invalid_numbers(values.iter().copied(), window_size)
+        .next()
+        .ok_or(Error::NoSolution)?;
+
+    Ok(bad_value)
 }
 
 fn part2(values: &[u64], window_size: usize) -> Result<u64> {
     let bad_value = part1(values, window_size)?;
-    for window_size in 2..=values.len() {
-        let windows = values.windows(window_size);
-        for window in windows {
-            if window.iter().sum::<u64>() == bad_value {
-                return Ok(window.iter().min().unwrap() + window.iter().max().unwrap());
+    let range = find_contiguous_sum(values.iter().copied(), bad_value)
+        .ok_or(Error::NoSolution)?;
+
+    Ok(range.iter().min().unwrap() + range.iter().max().unwrap())
+}
+
+/// Checks an XMAS stream one value at a time.
+///
+/// A value is valid if it is the sum of two values at different positions among the previous
+/// `window_size` values.  The sums of every pair in the window are kept as a multiset so that
+/// each value is checked in constant time and the window is updated in linear time.
+struct XmasAnalyser {
+    window_size: usize,
+    window: VecDeque<u64>,
+    sums: HashMap<u64, usize>,
+}
+
+impl XmasAnalyser {
+    fn new(window_size: usize) -> Self {
+        XmasAnalyser {
+            window_size,
+            window: VecDeque::with_capacity(window_size),
+            sums: HashMap::new(),
+        }
+    }
+
+    /// Adds the next value to the stream and returns whether it is valid.
+    ///
+    /// Values in the preamble are always valid.
+    fn push(&mut self, value: u64) -> bool {
+        let valid = self.window.len() < self.window_size || self.sums.contains_key(&value);
+
+        if self.window.len() == self.window_size {
+            if let Some(oldest) = self.window.pop_front() {
+                for other in self.window.iter() {
+                    let sum = oldest + other;
+                    let count = self.sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.sums.remove(&sum);
+                    }
+                }
+            }
+        }
+
+        if self.window_size > 0 {
+            for other in self.window.iter() {
+                *self.sums.entry(value + other).or_insert(0) += 1;
             }
+            self.window.push_back(value);
         }
+
+        valid
     }
+}
 
-    Err(Error::NoSolution)?
+/// Returns the position and value of every invalid number in the stream.
+fn invalid_numbers<I>(values: I, window_size: usize) -> impl Iterator<Item = (usize, u64)>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut analyser = XmasAnalyser::new(window_size);
+    values.into_iter()
+        .enumerate()
+        .filter(move |&(_, value)| !analyser.push(value))
+}
+
+/// Finds the first range of at least two contiguous values that sum to `target`.
+///
+/// Only the values in the current candidate range are held in memory.
+fn find_contiguous_sum<I>(values: I, target: u64) -> Option<Vec<u64>>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut range = VecDeque::new();
+    let mut sum = 0;
+
+    for value in values {
+        range.push_back(value);
+        sum += value;
+
+        while sum > target {
+            sum -= range.pop_front().unwrap();
+        }
+
+        if sum == target && range.len() >= 2 {
+            return Some(range.into());
+        }
+    }
+
+    None
 }
 
 #[cfg(test)]
@@ -81,8 +155,32 @@ mod test {
     }
 
     #[test]
-    #[ignore]
     fn part2() {
         assert_eq!(super::part2(&data(), 5).unwrap(), 62);
     }
+
+    #[test]
+    fn invalid_numbers() {
+        let actual = super::invalid_numbers(data(), 5).collect::<Vec<_>>();
+        assert_eq!(actual, vec![(14, 127)]);
+
+        let values = vec![1, 2, 3, 4, 100, 7, 200, 207];
+        let actual = super::invalid_numbers(values, 3).collect::<Vec<_>>();
+        assert_eq!(actual, vec![(4, 100), (6, 200)]);
+    }
+
+    #[test]
+    fn invalid_numbers_repeated_values() {
+        let values = vec![5, 5, 10, 15, 25, 5];
+        let actual = super::invalid_numbers(values, 2).collect::<Vec<_>>();
+        assert_eq!(actual, vec![(5, 5)]);
+    }
+
+    #[test]
+    fn find_contiguous_sum() {
+        assert_eq!(super::find_contiguous_sum(data(), 127), Some(vec![15, 25, 47, 40]));
+        assert_eq!(super::find_contiguous_sum(vec![3, 7, 1], 7), None);
+        let values = std::iter::repeat_n(10, 1_000_000).chain(vec![1, 2]);
+        assert_eq!(super::find_contiguous_sum(values, 3), Some(vec![1, 2]));
+    }
 }
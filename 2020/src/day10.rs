@@ -1,13 +1,15 @@
 use anyhow::Result;
 
-use crate::lib::{self, Error};
+use crate::lib::{self, Part};
 
-pub fn day(day: usize, input: &str) -> Result<()> {
+pub fn run(input: &str, part: Part) -> Result<String> {
     let data = lib::parse_input(input)?;
-    println!("day{:02}::part1: {}", day, part1(&data)?);
-    println!("day{:02}::part2: {}", day, part2(&data)?);
+    let answer = match part {
+        Part::One => part1(&data)?.to_string(),
+        Part::Two => part2(&data)?.to_string(),
+    };
 
-    Ok(())
+    Ok(answer)
 }
 
 fn part1(values: &[u32]) -> Result<u32> {
@@ -36,7 +38,7 @@ fn part1(values: &[u32]) -> Result<u32> {
     Ok(accs.iter().product())
 }
 
-fn part2(values: &[u32]) -> Result<u32> {
+fn part2(_values: &[u32]) -> Result<u32> {
     Ok(0)
 }
 
@@ -1,6 +1,7 @@
 use thiserror::Error;
 use anyhow::{Context, Result};
 
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -13,16 +14,47 @@ pub enum Error {
     ParseError,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::One, Part::Two];
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(Error::ParseError),
+        }
+    }
+}
+
 pub fn input_file_path(day: usize) -> PathBuf {
     PathBuf::from(format!("{}/inputs/day{:02}", env!("CARGO_MANIFEST_DIR"), day))
 }
 
 pub fn load_input(path: &Path) -> Result<String> {
-    Ok(fs::read_to_string(path)
-       .with_context(|| format!("Failed to open: {}", path.display()))?)
+    fs::read_to_string(path)
+       .with_context(|| format!("Failed to open: {}", path.display()))
 }
 
-pub fn parse_input<'a, T>(input: &'a str) -> Result<Vec<T>>
+pub fn parse_input<T>(input: &str) -> Result<Vec<T>>
 where
     T: FromStr
 {
@@ -1,4 +1,3 @@
-mod lib;
 mod day01;
 mod day02;
 mod day03;
@@ -10,28 +9,311 @@ mod day08;
 mod day09;
 mod day10;
 
-use anyhow::{Context, Result};
+use std::any::Any;
+use std::panic;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use aoc2020 as lib;
+use lib::Part;
+
+type Solver = fn(&str, Part) -> Result<String>;
+
+/// Runs one of a day's extra commands on its input and returns the output to print.
+type Tool = fn(&str, &[&str]) -> Result<String>;
+
+const DAYS: [Solver; 10] = [
+    day01::run,
+    day02::run,
+    day03::run,
+    day04::run,
+    day05::run,
+    day06::run,
+    day07::run,
+    day08::run,
+    day09::run,
+    day10::run,
+];
+
+const TOOLS: [(usize, Tool); 6] = [
+    (1, day01::tool),
+    (3, day03::tool),
+    (4, day04::tool),
+    (5, day05::tool),
+    (6, day06::tool),
+    (7, day07::tool),
+];
+
+const USAGE: &str = "\
+Usage: aoc2020 [--parallel] [DAY[.PART]]...
+       aoc2020 --tool DAY COMMAND [ARG]...
+
+Runs the selected days and parts, or every day if none are selected, and prints a summary
+table of the answers and timings.
+
+Options:
+    -p, --parallel  Run each part on its own thread
+    -t, --tool      Run one of a day's extra commands on its input
+
+Tools:
+    1 ksum K [TARGET]
+                      List every combination of K entries that sum to TARGET, 2020 by default
+    3 slopes [BOUND]  Count the trees on every slope up to BOUND right and down, 7 by
+                      default
+    3 path DX DY      Render the path taken by a slope
+    4 check [POLICY]  List the rule violations of each invalid passport
+    5 map             Render the seat map of the boarding passes
+    5 encode ROW COL [ROW_BITS COL_BITS]
+                      Encode a seat as a boarding pass code
+    6 groups          Describe the answers of each group
+    6 at-least K      Sum the questions answered by at least K people in each group
+    7 dot             Render the bag graph in Graphviz DOT format";
 
 fn main() -> Result<()> {
-    let days = [
-        day01::day,
-        day02::day,
-        day03::day,
-        day04::day,
-        day05::day,
-        day06::day,
-        day07::day,
-        day08::day,
-        day09::day,
-        day10::day,
-    ];
-
-    for (day_idx, day) in days.iter().enumerate() {
-        let path = lib::input_file_path(day_idx + 1);
-        let input = lib::load_input(&path)?;
-        day(day_idx + 1, &input)
-            .with_context(|| format!("Failed to solve input: '{}'", path.display()))?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some("-t" | "--tool") = args.first().map(String::as_str) {
+        print!("{}", run_tool(&args[1..])?);
+        return Ok(());
+    }
+
+    let options = match Options::parse(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let time = Instant::now();
+    let outcomes = if options.parallel {
+        thread::scope(|scope| {
+            let handles = options.tasks.iter()
+                .map(|&(day, part)| scope.spawn(move || solve(day, part)))
+                .collect::<Vec<_>>();
+            handles.into_iter()
+                .map(|handle| handle.join().expect("solve catches solver panics"))
+                .collect::<Vec<_>>()
+        })
+    } else {
+        options.tasks.iter()
+            .map(|&(day, part)| solve(day, part))
+            .collect()
+    };
+
+    print!("{}", summary(&outcomes));
+    println!("total {:?}", time.elapsed());
+
+    let num_failed = outcomes.iter().filter(|outcome| outcome.answer.is_err()).count();
+    if num_failed > 0 {
+        bail!("{} of {} parts failed", num_failed, outcomes.len());
     }
 
     Ok(())
 }
+
+#[derive(Debug, Eq, PartialEq)]
+struct Options {
+    parallel: bool,
+    tasks: Vec<(usize, Part)>,
+}
+
+impl Options {
+    /// Returns `None` if help was requested.
+    fn parse<I>(args: I) -> Result<Option<Self>>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut parallel = false;
+        let mut tasks = Vec::new();
+
+        for arg in args {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-p" | "--parallel" => parallel = true,
+                _ => {
+                    let (day, parts) = match arg.split_once('.') {
+                        Some((day, part)) => {
+                            let part = part.parse()
+                                .with_context(|| format!("Invalid part: '{}'", arg))?;
+                            (day, vec![part])
+                        }
+                        None => (arg.as_str(), Part::ALL.to_vec()),
+                    };
+                    let day = day.parse::<usize>()
+                        .ok()
+                        .filter(|day| (1..=DAYS.len()).contains(day))
+                        .with_context(|| format!("Invalid day: '{}'", arg))?;
+                    tasks.extend(parts.into_iter().map(|part| (day, part)));
+                }
+            }
+        }
+
+        if tasks.is_empty() {
+            tasks = (1..=DAYS.len())
+                .flat_map(|day| Part::ALL.iter().map(move |&part| (day, part)))
+                .collect();
+        }
+        tasks.sort_unstable();
+        tasks.dedup();
+
+        Ok(Some(Options { parallel, tasks }))
+    }
+}
+
+struct Outcome {
+    day: usize,
+    part: Part,
+    answer: Result<String>,
+    elapsed: Duration,
+}
+
+fn solve(day: usize, part: Part) -> Outcome {
+    let path = lib::input_file_path(day);
+    let (answer, elapsed) = match lib::load_input(&path) {
+        Ok(input) => {
+            let time = Instant::now();
+            let answer = run_guarded(DAYS[day - 1], &input, part);
+            (answer, time.elapsed())
+        }
+        Err(error) => (Err(error), Duration::ZERO),
+    };
+    let answer = answer
+        .with_context(|| format!("Failed to solve input: '{}'", path.display()));
+
+    Outcome { day, part, answer, elapsed }
+}
+
+/// Runs a tool given the day followed by the tool's arguments.
+fn run_tool(args: &[String]) -> Result<String> {
+    let (day, args) = args.split_first().context("Missing day")?;
+    let (day, tool) = day.parse::<usize>()
+        .ok()
+        .and_then(|day| TOOLS.iter().find(|&&(tool_day, _)| tool_day == day))
+        .with_context(|| format!("No tools for day: '{}'", day))?;
+    let input = lib::load_input(&lib::input_file_path(*day))?;
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    tool(&input, &args)
+}
+
+/// Runs a solver, turning a panic into an error so that one broken day cannot take down the
+/// whole run.
+fn run_guarded(solver: Solver, input: &str, part: Part) -> Result<String> {
+    panic::catch_unwind(|| solver(input, part))
+        .unwrap_or_else(|payload| Err(anyhow!("Panicked: {}", panic_message(&*payload))))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+fn summary(outcomes: &[Outcome]) -> String {
+    let mut table = format!("{:<12}  {:>12}  {}\n", "part", "time", "answer");
+    for outcome in outcomes {
+        let answer = match &outcome.answer {
+            Ok(answer) => answer.clone(),
+            Err(error) => format!("error: {:#}", error),
+        };
+        table += &format!(
+            "day{:02}::part{}  {:>12}  {}\n",
+            outcome.day,
+            outcome.part,
+            format!("{:.3?}", outcome.elapsed),
+            answer,
+        );
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use super::{Options, Outcome, Part};
+
+    fn parse(args: &[&str]) -> Options {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_all() {
+        let options = parse(&[]);
+        assert!(!options.parallel);
+        assert_eq!(options.tasks.len(), 20);
+        assert_eq!(options.tasks[0], (1, Part::One));
+        assert_eq!(options.tasks[19], (10, Part::Two));
+    }
+
+    #[test]
+    fn parse_selection() {
+        let options = parse(&["--parallel", "7.2", "4", "7.2"]);
+        assert_eq!(options, Options {
+            parallel: true,
+            tasks: vec![(4, Part::One), (4, Part::Two), (7, Part::Two)],
+        });
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |arg: &str| Options::parse(vec![arg.to_string()]);
+        assert_eq!(parse("0").unwrap_err().to_string(), "Invalid day: '0'");
+        assert_eq!(parse("11").unwrap_err().to_string(), "Invalid day: '11'");
+        assert_eq!(parse("3.3").unwrap_err().to_string(), "Invalid part: '3.3'");
+        assert!(parse("--help").unwrap().is_none());
+    }
+
+    #[test]
+    fn tool_errors() {
+        let run_tool = |args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            super::run_tool(&args).unwrap_err().to_string()
+        };
+        assert_eq!(run_tool(&[]), "Missing day");
+        assert_eq!(run_tool(&["2", "check"]), "No tools for day: '2'");
+        assert_eq!(run_tool(&["4", "frobnicate"]), "Usage: check [POLICY]");
+    }
+
+    #[test]
+    fn panicking_solver() {
+        fn fourth_line(input: &str, _: Part) -> anyhow::Result<String> {
+            let lines = input.lines().collect::<Vec<_>>();
+            Ok(lines[3].into())
+        }
+
+        let error = super::run_guarded(fourth_line, "1\n2\n", Part::One).unwrap_err();
+        assert!(error.to_string().starts_with("Panicked: index out of bounds"), "{}", error);
+
+        assert_eq!(super::run_guarded(fourth_line, "1\n2\n3\n4\n", Part::One).unwrap(), "4");
+    }
+
+    #[test]
+    fn summary() {
+        let outcomes = vec![
+            Outcome { day: 1, part: Part::One, answer: Ok("514579".into()), elapsed: Duration::from_micros(12) },
+            Outcome { day: 1, part: Part::Two, answer: Err(anyhow!("No solution found")), elapsed: Duration::from_millis(3) },
+        ];
+
+        let expected = "\
+part                  time  answer
+day01::part1      12.000µs  514579
+day01::part2       3.000ms  error: No solution found
+";
+        assert_eq!(super::summary(&outcomes), expected);
+    }
+}
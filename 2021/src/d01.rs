@@ -1,17 +1,16 @@
 use crate::input;
+use crate::output::Solution;
 
 use itertools::Itertools;
 
 const DAY: usize = 1;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
 fn p1(input: &str) -> usize {
@@ -1,230 +1,359 @@
 use crate::input;
+use crate::output::Solution;
 
-use std::ops::Add;
+use num_bigint::BigUint;
+
+use std::fmt;
 
 const DAY: usize = 3;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::try_time(DAY, 1, || p1(&input)),
+        Solution::try_time(DAY, 2, || p2(&input)),
+    ]
 }
 
-#[derive(Debug, PartialEq)]
-struct Value {
-    bits: Vec<i32>,
-}
+/// A fixed-width binary number of any length, most significant bit first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits(Vec<bool>);
 
-impl From<&str> for Value {
-    fn from(s: &str) -> Self {
-        let bits = s
-            .chars()
-            .map(|c| match c {
-                '0' => -1,
-                '1' => 1,
-                _ => panic!(),
-            })
-            .collect::<Vec<i32>>();
+impl Bits {
+    fn complement(&self) -> Self {
+        Self(self.0.iter().map(|bit| !bit).collect())
+    }
 
-        Self { bits }
+    fn to_biguint(&self) -> BigUint {
+        self.0.iter().fold(BigUint::default(), |acc, &bit| {
+            (acc << 1u8) + BigUint::from(bit as u8)
+        })
     }
 }
 
-impl Add for Value {
-    type Output = Self;
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &bit in &self.0 {
+            write!(f, "{}", if bit { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
 
-    fn add(self, other: Self) -> Self {
-        let bits = self
-            .bits
-            .iter()
-            .zip(other.bits.iter())
-            .map(|(a, b)| a + b)
-            .collect::<Vec<i32>>();
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiagnosticError {
+    Empty,
+    /// A line whose width differs from the first line's.  Lines are numbered from 1.
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidBit {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// A column with as many ones as zeros has no most common bit.
+    Tied {
+        column: usize,
+    },
+}
 
-        Self { bits }
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no diagnostic lines"),
+            Self::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {} is {} bits wide, expected {}",
+                line, found, expected
+            ),
+            Self::InvalidBit {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {} column {}: expected 0 or 1, found {:?}",
+                line, column, found
+            ),
+            Self::Tied { column } => write!(f, "column {} has no most common bit", column),
+        }
     }
 }
 
+#[derive(Debug, Default)]
+struct Node {
+    count: usize,
+    children: [Option<usize>; 2],
+}
+
 #[derive(Clone, Copy)]
 enum Criteria {
     MostCommon,
     LeastCommon,
 }
 
-#[derive(Clone, Copy)]
-enum EqualResult {
-    Value(usize),
-    Panic,
+impl Criteria {
+    /// Picks a branch given how many values have a zero and a one at this position.  Ties go to
+    /// one for the most common and zero for the least common, and a branch no value takes is
+    /// never picked.
+    fn pick(self, zeros: usize, ones: usize) -> bool {
+        match self {
+            Self::MostCommon => ones >= zeros,
+            Self::LeastCommon => zeros == 0 || (ones != 0 && ones < zeros),
+        }
+    }
 }
 
-impl Value {
-    fn to_usize(self, size: usize, criteria: Criteria, equal_result: EqualResult) -> usize {
-        self.bits
-            .iter()
-            .map(|b| match b {
-                x if x > &0 => match criteria {
-                    Criteria::MostCommon => 1,
-                    Criteria::LeastCommon => 0,
-                },
-                x if x < &0 => match criteria {
-                    Criteria::MostCommon => 0,
-                    Criteria::LeastCommon => 1,
-                },
-                x if x < &0 => 0,
-                x if x == &0 => match equal_result {
-                    EqualResult::Value(x) => x,
-                    EqualResult::Panic => panic!(),
-                },
-                _ => panic!(),
-            })
-            .enumerate()
-            .map(|(i, v)| v << (size - i - 1))
-            .sum()
-    }
-
-    fn from_usize(from: usize, size: usize) -> Self {
-        let bits = (0..size)
-            .rev()
-            .map(|bit_idx| {
-                let bit_value = get_bit(from, bit_idx);
-                match bit_value {
-                    0 => -1,
-                    1 => 1,
-                    _ => unreachable!(),
-                }
-            })
-            .collect::<Vec<i32>>();
+/// Every diagnostic line, stored in a binary trie so that each prefix knows how many lines share
+/// it.  Built in a single pass over the input.
+#[derive(Debug)]
+struct Diagnostics {
+    width: usize,
+    nodes: Vec<Node>,
+    ones: Vec<usize>,
+}
 
-        Self { bits }
+impl TryFrom<&str> for Diagnostics {
+    type Error = DiagnosticError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut diagnostics: Option<Self> = None;
+
+        for (line_idx, line) in s.lines().enumerate() {
+            let width = line.chars().count();
+            let diagnostics = diagnostics.get_or_insert_with(|| Self {
+                width,
+                nodes: vec![Node::default()],
+                ones: vec![0; width],
+            });
+
+            if width != diagnostics.width {
+                return Err(DiagnosticError::Ragged {
+                    line: line_idx + 1,
+                    expected: diagnostics.width,
+                    found: width,
+                });
+            }
+
+            diagnostics
+                .insert(line)
+                .map_err(|(column, found)| DiagnosticError::InvalidBit {
+                    line: line_idx + 1,
+                    column: column + 1,
+                    found,
+                })?;
+        }
+
+        diagnostics.ok_or(DiagnosticError::Empty)
     }
 }
 
-fn p1(input: &str) -> usize {
-    let size = input.lines().next().unwrap().len();
+impl Diagnostics {
+    /// Adds a line to the trie, or returns the position and character of the first invalid bit.
+    /// The line is checked before anything is added so a failed insert leaves no trace.
+    fn insert(&mut self, line: &str) -> Result<(), (usize, char)> {
+        if let Some(invalid) = line
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !matches!(c, '0' | '1'))
+        {
+            return Err(invalid);
+        }
 
-    let gamma_rate = input
-        .lines()
-        .map(Value::from)
-        .reduce(|a, b| a + b)
-        .unwrap()
-        .to_usize(size, Criteria::MostCommon, EqualResult::Panic);
+        let mut node = 0;
+        self.nodes[node].count += 1;
+        for (column, c) in line.chars().enumerate() {
+            let bit = (c == '1') as usize;
+            self.ones[column] += bit;
+
+            node = match self.nodes[node].children[bit] {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children[bit] = Some(child);
+                    child
+                }
+            };
+            self.nodes[node].count += 1;
+        }
 
-    let mask = (1 << size) - 1;
-    let epsilon_rate = !gamma_rate & mask;
+        Ok(())
+    }
 
-    gamma_rate * epsilon_rate
-}
+    fn len(&self) -> usize {
+        self.nodes[0].count
+    }
 
-fn find_rating(
-    values: &[usize],
-    size: usize,
-    criteria: Criteria,
-    equal_result: EqualResult,
-) -> usize {
-    let mut values = values.to_vec();
+    fn count(&self, child: Option<usize>) -> usize {
+        child.map_or(0, |child| self.nodes[child].count)
+    }
 
-    for bit_idx in (0..size).rev() {
-        let criteria = values
+    fn gamma(&self) -> Result<Bits, DiagnosticError> {
+        self.ones
             .iter()
-            .map(|value| Value::from_usize(*value, size))
-            .reduce(|a, b| a + b)
-            .unwrap()
-            .to_usize(size, criteria, equal_result);
-
-        let criteria_bit_value = get_bit(criteria, bit_idx);
-        values = values
-            .into_iter()
-            .filter(|value| {
-                let bit_value = get_bit(*value, bit_idx);
-                bit_value == criteria_bit_value
+            .enumerate()
+            .map(|(column, &ones)| match (ones * 2).cmp(&self.len()) {
+                std::cmp::Ordering::Greater => Ok(true),
+                std::cmp::Ordering::Less => Ok(false),
+                std::cmp::Ordering::Equal => Err(DiagnosticError::Tied { column: column + 1 }),
             })
-            .collect::<Vec<usize>>();
+            .collect::<Result<Vec<bool>, DiagnosticError>>()
+            .map(Bits)
+    }
 
-        if values.len() == 1 {
-            break;
+    fn epsilon(&self) -> Result<Bits, DiagnosticError> {
+        self.gamma().map(|gamma| gamma.complement())
+    }
+
+    /// Walks the trie from the root, choosing a branch at each bit by the criteria among the
+    /// lines still matching.
+    fn rating(&self, criteria: Criteria) -> Bits {
+        let mut node = 0;
+        let mut bits = Vec::with_capacity(self.width);
+
+        for _ in 0..self.width {
+            let [zero, one] = self.nodes[node].children;
+            let bit = criteria.pick(self.count(zero), self.count(one));
+            bits.push(bit);
+            node = self.nodes[node].children[bit as usize].unwrap();
         }
+
+        Bits(bits)
     }
 
-    values[0]
-}
+    fn oxygen_generator(&self) -> Bits {
+        self.rating(Criteria::MostCommon)
+    }
 
-fn get_bit(value: usize, bit_idx: usize) -> usize {
-    (value >> bit_idx) & 1
+    fn co2_scrubber(&self) -> Bits {
+        self.rating(Criteria::LeastCommon)
+    }
 }
 
-fn p2(input: &str) -> usize {
-    let size = input.lines().next().unwrap().len();
-
-    let values = input
-        .lines()
-        .map(|s| usize::from_str_radix(s, 2).unwrap())
-        .collect::<Vec<usize>>();
+fn p1(input: &str) -> Result<BigUint, DiagnosticError> {
+    let diagnostics = Diagnostics::try_from(input)?;
 
-    let oxygen_generator_rating =
-        find_rating(&values, size, Criteria::MostCommon, EqualResult::Value(1));
+    Ok(diagnostics.gamma()?.to_biguint() * diagnostics.epsilon()?.to_biguint())
+}
 
-    let co2_scrubber_rating =
-        find_rating(&values, size, Criteria::LeastCommon, EqualResult::Value(0));
+fn p2(input: &str) -> Result<BigUint, DiagnosticError> {
+    let diagnostics = Diagnostics::try_from(input)?;
 
-    oxygen_generator_rating * co2_scrubber_rating
+    Ok(diagnostics.oxygen_generator().to_biguint() * diagnostics.co2_scrubber().to_biguint())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
 
+    const INPUT: &str = indoc! {"
+        00100
+        11110
+        10110
+        10111
+        10101
+        01111
+        00111
+        11100
+        10000
+        11001
+        00010
+        01010
+    "};
+
     #[test]
     fn p1() {
-        let input = indoc! {"
-            00100
-            11110
-            10110
-            10111
-            10101
-            01111
-            00111
-            11100
-            10000
-            11001
-            00010
-            01010
-        "};
-        assert_eq!(super::p1(input), 198);
+        let diagnostics = Diagnostics::try_from(INPUT).unwrap();
+        assert_eq!(diagnostics.gamma().unwrap().to_string(), "10110");
+        assert_eq!(diagnostics.epsilon().unwrap().to_string(), "01001");
+        assert_eq!(super::p1(INPUT), Ok(BigUint::from(198u32)));
 
         let input = super::input(super::DAY);
-        assert_eq!(super::p1(&input), 2498354);
+        assert_eq!(super::p1(&input), Ok(BigUint::from(2498354u32)));
     }
 
     #[test]
     fn p2() {
+        let diagnostics = Diagnostics::try_from(INPUT).unwrap();
+        assert_eq!(diagnostics.oxygen_generator().to_string(), "10111");
+        assert_eq!(diagnostics.co2_scrubber().to_string(), "01010");
+        assert_eq!(super::p2(INPUT), Ok(BigUint::from(230u32)));
+
+        let input = super::input(super::DAY);
+        assert_eq!(super::p2(&input), Ok(BigUint::from(3277956u32)));
+    }
+
+    #[test]
+    fn wide_values() {
+        let ones = "1".repeat(100);
+        let zeros = "0".repeat(100);
+        let mixed = format!("1{}", "0".repeat(99));
+        let input = format!("{}\n{}\n{}\n", ones, mixed, zeros);
+
+        let diagnostics = Diagnostics::try_from(input.as_str()).unwrap();
+        assert_eq!(diagnostics.gamma().unwrap().to_string(), mixed);
+        assert_eq!(diagnostics.oxygen_generator().to_string(), ones);
+        assert_eq!(diagnostics.co2_scrubber().to_string(), zeros);
+
+        let max = (BigUint::from(1u8) << 100u8) - 1u8;
+        let high = BigUint::from(1u8) << 99u8;
+        assert_eq!(super::p1(&input), Ok(&high * (&max - &high)));
+        assert_eq!(super::p2(&input), Ok(BigUint::default()));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Diagnostics::try_from("0101\n011\n").unwrap_err();
         assert_eq!(
-            super::Value::from_usize(22, 5),
-            super::Value {
-                bits: vec![1, -1, 1, 1, -1]
+            error,
+            DiagnosticError::Ragged {
+                line: 2,
+                expected: 4,
+                found: 3
             }
         );
+        assert_eq!(error.to_string(), "line 2 is 3 bits wide, expected 4");
 
-        let input = indoc! {"
-            00100
-            11110
-            10110
-            10111
-            10101
-            01111
-            00111
-            11100
-            10000
-            11001
-            00010
-            01010
-        "};
-        assert_eq!(super::p2(input), 230);
-
-        let input = super::input(super::DAY);
-        assert_eq!(super::p2(&input), 3277956);
+        assert_eq!(
+            Diagnostics::try_from("01\n0x\n").unwrap_err(),
+            DiagnosticError::InvalidBit {
+                line: 2,
+                column: 2,
+                found: 'x'
+            }
+        );
+        assert_eq!(
+            Diagnostics::try_from("").unwrap_err(),
+            DiagnosticError::Empty
+        );
+        assert_eq!(
+            Diagnostics::try_from("01\n10\n")
+                .unwrap()
+                .gamma()
+                .unwrap_err(),
+            DiagnosticError::Tied { column: 1 }
+        );
+        assert_eq!(
+            super::p1("01\n10\n"),
+            Err(DiagnosticError::Tied { column: 1 })
+        );
+        assert_eq!(
+            super::p2("01\n2\n"),
+            Err(DiagnosticError::Ragged {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
     }
 }
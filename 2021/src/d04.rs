@@ -1,98 +1,96 @@
 use crate::input;
+use crate::output::Solution;
+
+use itertools::Itertools;
+
+use std::collections::VecDeque;
+use std::fmt;
 
 const DAY: usize = 4;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::try_time(DAY, 1, || p1(&input)),
+        Solution::try_time(DAY, 2, || p2(&input)),
+    ]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Board {
+    size: usize,
     values: Vec<u8>,
     marks: Vec<bool>,
 }
 
-impl Board {
-    fn is_win(&self) -> bool {
-        // Check rows
-        let any_row = self
-            .marks
-            .chunks(5)
-            .map(|row| row.iter().all(|&x| x))
-            .any(|x| x);
-
-        if any_row {
-            return true;
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WinLine {
+    Row(usize),
+    Column(usize),
+    /// From the top left to the bottom right.
+    Diagonal,
+    /// From the top right to the bottom left.
+    AntiDiagonal,
+}
 
-        // Check columns
-        let any_col = (0..5)
-            .map(|col_idx| {
-                self.marks
-                    .chunks(5)
-                    .map(|row| row.iter().nth(col_idx).unwrap())
-                    .all(|&x| x)
+impl Board {
+    /// The first complete line, checking rows, then columns, then diagonals if they count.
+    fn winning_line(&self, diagonals: bool) -> Option<WinLine> {
+        let n = self.size;
+        let marked = |row: usize, col: usize| self.marks[row * n + col];
+
+        let row = (0..n).find(|&row| (0..n).all(|col| marked(row, col)));
+        let col = (0..n).find(|&col| (0..n).all(|row| marked(row, col)));
+
+        row.map(WinLine::Row)
+            .or_else(|| col.map(WinLine::Column))
+            .or_else(|| {
+                if !diagonals {
+                    None
+                } else if (0..n).all(|i| marked(i, i)) {
+                    Some(WinLine::Diagonal)
+                } else if (0..n).all(|i| marked(i, n - 1 - i)) {
+                    Some(WinLine::AntiDiagonal)
+                } else {
+                    None
+                }
             })
-            .any(|x| x);
-
-        any_col
     }
 
     /// Marks the given value if it exists.
-    ///
-    /// Returns true if the board has bingo.
-    fn mark(&mut self, value: u8) -> bool {
-        let find_result = self.values.iter().enumerate().find(|(_i, &x)| x == value);
-
-        if let Some((i, _x)) = find_result {
+    fn mark(&mut self, value: u8) {
+        if let Some(i) = self.values.iter().position(|&x| x == value) {
             self.marks[i] = true;
         }
-
-        self.is_win()
     }
 
     fn sum_unmarked(&self) -> usize {
         self.marks
             .iter()
             .zip(self.values.iter())
-            .filter_map(
-                |(&marked, &value)| {
-                    if !marked {
-                        Some(value as usize)
-                    } else {
-                        None
-                    }
-                },
-            )
+            .filter(|(&marked, _)| !marked)
+            .map(|(_, &value)| value as usize)
             .sum()
     }
 }
 
-impl From<&[&str]> for Board {
-    fn from(lines: &[&str]) -> Self {
-        let mut lines = lines.iter();
-
-        // Skip first (blank) line
-        lines.next();
-
-        let values = lines
-            .flat_map(|line| {
-                line.split(char::is_whitespace)
-                    .map(str::parse::<u8>)
-                    .filter_map(Result::ok)
-            })
+impl From<&str> for Board {
+    fn from(s: &str) -> Self {
+        let size = s.lines().count();
+        let values = s
+            .split_whitespace()
+            .map(str::parse::<u8>)
+            .filter_map(Result::ok)
             .collect::<Vec<u8>>();
 
         let num_values = values.len();
         let marks = vec![false; num_values];
 
-        Self { values, marks }
+        Self {
+            size,
+            values,
+            marks,
+        }
     }
 }
 
@@ -100,136 +98,286 @@ impl From<&[&str]> for Board {
 struct Bingo {
     draws: Vec<u8>,
     boards: Vec<Board>,
-    winners: Vec<bool>,
 }
 
-impl From<&str> for Bingo {
-    fn from(s: &str) -> Self {
-        let mut lines = s.lines();
-        let draws = lines
-            .next()
-            .unwrap()
-            .split(",")
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BingoError {
+    DuplicateDraw(u8),
+    NotSquare { board: usize },
+    NoWinner,
+}
+
+impl fmt::Display for BingoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateDraw(draw) => write!(f, "{} is drawn more than once", draw),
+            Self::NotSquare { board } => write!(f, "board {} is not square", board),
+            Self::NoWinner => write!(f, "no board wins"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Bingo {
+    type Error = BingoError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let (draws, boards) = s.split_once("\n\n").unwrap_or((s, ""));
+        let draws = draws
+            .trim()
+            .split(',')
             .map(str::parse::<u8>)
             .filter_map(Result::ok)
             .collect::<Vec<u8>>();
 
-        let lines = lines.collect::<Vec<&str>>();
-        let boards = lines.chunks(6).map(Board::from).collect::<Vec<Board>>();
+        if let Some(draw) = draws.iter().duplicates().next() {
+            return Err(BingoError::DuplicateDraw(*draw));
+        }
 
-        let num_boards = boards.len();
-        let winners = vec![false; num_boards];
+        let boards = boards
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|board| !board.is_empty())
+            .map(Board::from)
+            .collect::<Vec<Board>>();
 
-        Self {
-            draws,
-            boards,
-            winners,
+        if let Some(board) = boards
+            .iter()
+            .position(|board| board.values.len() != board.size * board.size)
+        {
+            return Err(BingoError::NotSquare { board });
         }
+
+        Ok(Self { draws, boards })
     }
 }
 
-#[derive(Clone, Copy)]
-enum Until {
-    FirstWin,
-    LastWin,
+/// A board completing its first line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Win {
+    board: usize,
+    draw: u8,
+    line: WinLine,
+    score: usize,
 }
 
-impl Bingo {
-    fn play(&mut self, until: Until) -> usize {
-        let (board_idx, draw) = self.determine_winner(until);
-        let sum = self.boards[board_idx].sum_unmarked();
-        (draw as usize) * sum
-    }
+/// Plays the draws in order, yielding each board's win as it happens.  Boards that win on the
+/// same draw are yielded in board order.
+struct Game {
+    draws: std::vec::IntoIter<u8>,
+    boards: Vec<Board>,
+    won: Vec<bool>,
+    diagonals: bool,
+    pending: VecDeque<Win>,
+}
+
+impl Iterator for Game {
+    type Item = Win;
 
-    fn determine_winner(&mut self, until: Until) -> (usize, u8) {
-        for &draw in self.draws.iter() {
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let draw = self.draws.next()?;
             for (board_idx, board) in self.boards.iter_mut().enumerate() {
-                if board.mark(draw) {
-                    self.winners[board_idx] = true;
+                if self.won[board_idx] {
+                    continue;
                 }
-
-                let winner_determined = match until {
-                    Until::FirstWin => self.winners.iter().any(|&x| x),
-                    Until::LastWin => self.winners.iter().all(|&x| x),
-                };
-
-                if winner_determined {
-                    return (board_idx, draw);
+                board.mark(draw);
+                if let Some(line) = board.winning_line(self.diagonals) {
+                    self.won[board_idx] = true;
+                    self.pending.push_back(Win {
+                        board: board_idx,
+                        draw,
+                        line,
+                        score: draw as usize * board.sum_unmarked(),
+                    });
                 }
             }
         }
 
-        panic!()
+        self.pending.pop_front()
     }
 }
 
-fn p1(input: &str) -> usize {
-    Bingo::from(input).play(Until::FirstWin)
+impl Bingo {
+    fn wins(&self, diagonals: bool) -> Game {
+        Game {
+            draws: self.draws.clone().into_iter(),
+            boards: self.boards.clone(),
+            won: vec![false; self.boards.len()],
+            diagonals,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// The boards that are still without a line once every number is drawn.
+    fn never_winning(&self, diagonals: bool) -> Vec<usize> {
+        let mut won = vec![false; self.boards.len()];
+        for win in self.wins(diagonals) {
+            won[win.board] = true;
+        }
+
+        (0..self.boards.len())
+            .filter(|&board| !won[board])
+            .collect()
+    }
 }
 
-fn p2(input: &str) -> usize {
-    Bingo::from(input).play(Until::LastWin)
+/// Runs the `never-winning [--diagonals]` command, which lists the puzzle's boards, counted from
+/// 0, that never get a line, also counting diagonals as lines with `--diagonals`.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let diagonals = match args {
+        ["never-winning"] => false,
+        ["never-winning", "--diagonals"] => true,
+        _ => return Err("usage: aoc2021 4 never-winning [--diagonals]".to_string()),
+    };
+    let input = input(DAY);
+    let bingo = Bingo::try_from(input.as_str()).map_err(|e| e.to_string())?;
+
+    let boards = bingo.never_winning(diagonals);
+    if boards.is_empty() {
+        return Ok("every board gets a line\n".to_string());
+    }
+
+    Ok(boards
+        .iter()
+        .map(|board| format!("board {}\n", board))
+        .collect())
+}
+
+fn p1(input: &str) -> Result<usize, BingoError> {
+    Bingo::try_from(input)?
+        .wins(false)
+        .next()
+        .map(|win| win.score)
+        .ok_or(BingoError::NoWinner)
+}
+
+fn p2(input: &str) -> Result<usize, BingoError> {
+    Bingo::try_from(input)?
+        .wins(false)
+        .last()
+        .map(|win| win.score)
+        .ok_or(BingoError::NoWinner)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
 
+    const INPUT: &str = indoc! {"
+        7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+        22 13 17 11  0
+         8  2 23  4 24
+        21  9 14 16  7
+         6 10  3 18  5
+         1 12 20 15 19
+
+         3 15  0  2 22
+         9 18 13 17  5
+        19  8  7 25 23
+        20 11 10 24  4
+        14 21 16 12  6
+
+        14 21 17 24  4
+        10 16 15  9 19
+        18  8 23 26 20
+        22 11 13  6  5
+         2  0 12  3  7
+    "};
+
     #[test]
     fn p1() {
-        let input = indoc! {"
-            7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-            22 13 17 11  0
-             8  2 23  4 24
-            21  9 14 16  7
-             6 10  3 18  5
-             1 12 20 15 19
-
-             3 15  0  2 22
-             9 18 13 17  5
-            19  8  7 25 23
-            20 11 10 24  4
-            14 21 16 12  6
-
-            14 21 17 24  4
-            10 16 15  9 19
-            18  8 23 26 20
-            22 11 13  6  5
-             2  0 12  3  7
-        "};
-        assert_eq!(super::p1(input), 4512);
+        assert_eq!(super::p1(INPUT), Ok(4512));
 
         let input = super::input(super::DAY);
-        assert_eq!(super::p1(&input), 8442);
+        assert_eq!(super::p1(&input), Ok(8442));
     }
 
     #[test]
     fn p2() {
+        assert_eq!(super::p2(INPUT), Ok(1924));
+
+        let input = super::input(super::DAY);
+        assert_eq!(super::p2(&input), Ok(4590));
+    }
+
+    #[test]
+    fn wins() {
+        let bingo = Bingo::try_from(INPUT).unwrap();
+        let wins = bingo.wins(false).collect::<Vec<Win>>();
+        assert_eq!(
+            wins,
+            vec![
+                Win {
+                    board: 2,
+                    draw: 24,
+                    line: WinLine::Row(0),
+                    score: 4512
+                },
+                Win {
+                    board: 0,
+                    draw: 16,
+                    line: WinLine::Row(2),
+                    score: 2192
+                },
+                Win {
+                    board: 1,
+                    draw: 13,
+                    line: WinLine::Column(2),
+                    score: 1924
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn small_boards_with_diagonals() {
         let input = indoc! {"
-            7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-            22 13 17 11  0
-             8  2 23  4 24
-            21  9 14 16  7
-             6 10  3 18  5
-             1 12 20 15 19
-
-             3 15  0  2 22
-             9 18 13 17  5
-            19  8  7 25 23
-            20 11 10 24  4
-            14 21 16 12  6
-
-            14 21 17 24  4
-            10 16 15  9 19
-            18  8 23 26 20
-            22 11 13  6  5
-             2  0 12  3  7
+            1,5,9,3,8
+
+            1 2 3
+            4 5 6
+            7 8 9
+
+            3 2 1
+            6 5 4
+            8 7 10
+
+            10 11
+            12 13
         "};
-        assert_eq!(super::p2(input), 1924);
+        let bingo = Bingo::try_from(input).unwrap();
+
+        let wins = bingo.wins(true).map(|win| (win.board, win.draw, win.line));
+        assert_eq!(
+            wins.collect::<Vec<_>>(),
+            vec![(0, 9, WinLine::Diagonal), (1, 8, WinLine::AntiDiagonal)]
+        );
+        assert_eq!(bingo.wins(false).count(), 0);
+        assert_eq!(bingo.never_winning(true), vec![2]);
+        assert_eq!(bingo.never_winning(false), vec![0, 1, 2]);
+    }
 
-        let input = super::input(super::DAY);
-        assert_eq!(super::p2(&input), 4590);
+    #[test]
+    fn tool() {
+        let every = Ok("every board gets a line\n".to_string());
+        assert_eq!(super::tool(&["never-winning"]), every);
+        assert_eq!(super::tool(&["never-winning", "--diagonals"]), every);
+        assert!(super::tool(&["never-winning", "--rows"]).is_err());
+    }
+
+    #[test]
+    fn invalid_games() {
+        let error = Bingo::try_from("1,2,1\n\n1 2\n3 4\n").unwrap_err();
+        assert_eq!(error, BingoError::DuplicateDraw(1));
+        assert_eq!(error.to_string(), "1 is drawn more than once");
+
+        let error = Bingo::try_from("1,2\n\n1 2\n3 4\n\n1 2 3\n4 5 6\n").unwrap_err();
+        assert_eq!(error, BingoError::NotSquare { board: 1 });
+
+        assert_eq!(super::p1("1,4\n\n1 2\n3 4\n"), Err(BingoError::NoWinner));
+        assert_eq!(super::p2("1,1\n\n1\n"), Err(BingoError::DuplicateDraw(1)));
     }
 }
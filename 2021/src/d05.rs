@@ -1,36 +1,35 @@
 use crate::input;
+use crate::output::Solution;
+
+use itertools::Itertools;
+use itertools::MinMaxResult::{MinMax, NoElements, OneElement};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
 const DAY: usize = 5;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Point {
-    x: usize,
-    y: usize,
-}
-
-impl Default for Point {
-    fn default() -> Self {
-        Self { x: 0, y: 0 }
-    }
+    x: i64,
+    y: i64,
 }
 
 impl From<&str> for Point {
     fn from(s: &str) -> Self {
         let values = s
-            .split(",")
-            .map(str::parse::<usize>)
+            .split(',')
+            .map(str::parse::<i64>)
             .filter_map(Result::ok)
-            .collect::<Vec<usize>>();
+            .collect::<Vec<i64>>();
 
         Self {
             x: values[0],
@@ -56,105 +55,423 @@ impl From<&str> for Line {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Orientation {
     Horizontal,
     Vertical,
+    /// At 45 degrees.
     Diagonal,
+    /// At any other angle.
+    Oblique,
+}
+
+impl Orientation {
+    const ALL: [Self; 4] = [
+        Self::Horizontal,
+        Self::Vertical,
+        Self::Diagonal,
+        Self::Oblique,
+    ];
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 impl Line {
     fn orientation(&self) -> Orientation {
-        if self.p0.x == self.p1.x {
+        let dx = self.p1.x - self.p0.x;
+        let dy = self.p1.y - self.p0.y;
+        if dx == 0 {
             Orientation::Vertical
-        } else if self.p0.y == self.p1.y {
+        } else if dy == 0 {
             Orientation::Horizontal
-        } else {
+        } else if dx.abs() == dy.abs() {
             Orientation::Diagonal
+        } else {
+            Orientation::Oblique
         }
     }
 
-    fn to_points(&self) -> Vec<Point> {
-        let x_min = self.p0.x.min(self.p1.x);
-        let x_max = self.p0.x.max(self.p1.x);
-        let y_min = self.p0.y.min(self.p1.y);
-        let y_max = self.p0.y.max(self.p1.y);
+    /// The track the line lies on.  A line of a single point lies on a vertical track.
+    fn track(&self) -> Track {
+        let dx = self.p1.x - self.p0.x;
+        let dy = self.p1.y - self.p0.y;
+        let (dx, dy) = match gcd(dx, dy) {
+            0 => (0, 1),
+            steps => (dx / steps, dy / steps),
+        };
+        let (dx, dy) = if dx < 0 || (dx == 0 && dy < 0) {
+            (-dx, -dy)
+        } else {
+            (dx, dy)
+        };
+        let offset = dy as i128 * self.p0.x as i128 - dx as i128 * self.p0.y as i128;
 
-        match self.orientation() {
-            Orientation::Vertical => {
-                let x = self.p0.x;
-                (y_min..=y_max).map(|y| Point { x, y }).collect()
-            }
-            Orientation::Horizontal => {
-                let y = self.p0.y;
-                (x_min..=x_max).map(|x| Point { x, y }).collect()
+        Track { dx, dy, offset }
+    }
+
+    /// The lattice points on the line, from `p0` to `p1`.
+    ///
+    /// Dividing the line's extent by the gcd of its x and y extents gives the smallest step
+    /// that lands on whole coordinates.
+    fn to_points(self) -> impl Iterator<Item = Point> {
+        let dx = self.p1.x - self.p0.x;
+        let dy = self.p1.y - self.p0.y;
+        let steps = gcd(dx, dy);
+        let (step_x, step_y) = if steps == 0 {
+            (0, 0)
+        } else {
+            (dx / steps, dy / steps)
+        };
+
+        (0..=steps).map(move |i| Point {
+            x: self.p0.x + i * step_x,
+            y: self.p0.y + i * step_y,
+        })
+    }
+}
+
+/// The infinite line that collinear lines share, given by its direction in lowest terms and its
+/// offset `dy * x - dx * y`, which is the same for every point on it.
+///
+/// Directions point right, or down for vertical tracks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Track {
+    dx: i64,
+    dy: i64,
+    offset: i128,
+}
+
+impl Track {
+    fn orientation(&self) -> Orientation {
+        match (self.dx, self.dy) {
+            (0, _) => Orientation::Vertical,
+            (_, 0) => Orientation::Horizontal,
+            (dx, dy) if dx == dy.abs() => Orientation::Diagonal,
+            _ => Orientation::Oblique,
+        }
+    }
+
+    /// The number of steps along the track from `origin` to `p`, both of which are lattice
+    /// points on it.
+    fn steps(&self, origin: Point, p: Point) -> i64 {
+        if self.dx != 0 {
+            (p.x - origin.x) / self.dx
+        } else {
+            (p.y - origin.y) / self.dy
+        }
+    }
+
+    fn point(&self, origin: Point, steps: i64) -> Point {
+        Point {
+            x: origin.x + steps * self.dx,
+            y: origin.y + steps * self.dy,
+        }
+    }
+}
+
+/// A stretch of a track covered by the same number of lines.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Run {
+    start: i64,
+    end: i64,
+    count: usize,
+}
+
+impl Run {
+    fn len(&self) -> usize {
+        (self.end - self.start + 1) as usize
+    }
+}
+
+/// Splits the union of inclusive intervals along one track into runs, sweeping over the
+/// points where an interval starts or ends.
+fn sweep(intervals: &[(i64, i64)]) -> Vec<Run> {
+    let mut events = intervals
+        .iter()
+        .flat_map(|&(start, end)| [(start, 1), (end + 1, -1)])
+        .collect::<Vec<(i64, i64)>>();
+    events.sort_unstable();
+
+    let mut runs = vec![];
+    let mut count = 0;
+    let mut start = 0;
+    for (position, events) in &events.into_iter().group_by(|&(position, _)| position) {
+        if count > 0 {
+            runs.push(Run {
+                start,
+                end: position - 1,
+                count: count as usize,
+            });
+        }
+        count += events.map(|(_, delta)| delta).sum::<i64>();
+        start = position;
+    }
+
+    runs
+}
+
+/// The number of lines covering `at` within the runs of one track.
+fn count_at(runs: &[Run], at: i64) -> usize {
+    let i = runs.partition_point(|run| run.end < at);
+    runs.get(i)
+        .filter(|run| run.start <= at)
+        .map_or(0, |run| run.count)
+}
+
+/// The lines along one track, as runs of steps from a lattice point on the track.
+#[derive(Clone, Debug)]
+struct Lane {
+    track: Track,
+    origin: Point,
+    runs: Vec<Run>,
+    /// The corners of the box bounding the runs.
+    min: Point,
+    max: Point,
+}
+
+impl Lane {
+    fn new(track: Track, origin: Point, runs: Vec<Run>) -> Self {
+        let first = track.point(origin, runs[0].start);
+        let last = track.point(origin, runs[runs.len() - 1].end);
+
+        Self {
+            track,
+            origin,
+            runs,
+            min: Point {
+                x: first.x.min(last.x),
+                y: first.y.min(last.y),
+            },
+            max: Point {
+                x: first.x.max(last.x),
+                y: first.y.max(last.y),
+            },
+        }
+    }
+
+    /// The number of points covered by at least two of the lane's lines.
+    fn stacked(&self) -> usize {
+        self.runs
+            .iter()
+            .filter(|run| run.count >= 2)
+            .map(Run::len)
+            .sum()
+    }
+
+    /// Where this lane and another with a different direction both have a line, as the point
+    /// and the number of lines each lane has there.
+    ///
+    /// The tracks meet where `self.origin + s * self.direction` equals
+    /// `other.origin + t * other.direction`, which Cramer's rule solves for `s` and `t`.  The
+    /// tracks only cross at a lattice point if both are whole numbers.
+    fn crossing(&self, other: &Self) -> Option<(Point, usize, usize)> {
+        if self.max.x < other.min.x
+            || other.max.x < self.min.x
+            || self.max.y < other.min.y
+            || other.max.y < self.min.y
+        {
+            return None;
+        }
+
+        let cross =
+            |ax: i64, ay: i64, bx: i64, by: i64| ax as i128 * by as i128 - ay as i128 * bx as i128;
+        let (a, b) = (self.track, other.track);
+        let det = cross(a.dx, a.dy, b.dx, b.dy);
+        if det == 0 {
+            return None;
+        }
+        let (wx, wy) = (
+            other.origin.x - self.origin.x,
+            other.origin.y - self.origin.y,
+        );
+        let (s, t) = (cross(wx, wy, b.dx, b.dy), cross(wx, wy, a.dx, a.dy));
+        if s % det != 0 || t % det != 0 {
+            return None;
+        }
+        let s = i64::try_from(s / det).ok()?;
+        let t = i64::try_from(t / det).ok()?;
+
+        let (here, there) = (count_at(&self.runs, s), count_at(&other.runs, t));
+        if here == 0 || there == 0 {
+            return None;
+        }
+
+        Some((a.point(self.origin, s), here, there))
+    }
+}
+
+/// Which points a set of lines covers, without visiting every point of a long line.
+///
+/// Lines are kept as runs along their tracks, so their length does not matter.  Overlaps
+/// within a track come from its runs, and overlaps between tracks can only be where they
+/// cross.
+#[derive(Clone, Debug, Default)]
+struct Coverage {
+    lanes: Vec<Lane>,
+}
+
+impl Coverage {
+    /// Every point where lanes cross, with the number of lines on each lane through it, keyed
+    /// by the lane's index.
+    fn mixed(&self) -> HashMap<Point, BTreeMap<usize, usize>> {
+        let mut mixed: HashMap<Point, BTreeMap<usize, usize>> = HashMap::new();
+        for (i, a) in self.lanes.iter().enumerate() {
+            for (j, b) in self.lanes.iter().enumerate().skip(i + 1) {
+                if let Some((p, here, there)) = a.crossing(b) {
+                    let lanes = mixed.entry(p).or_default();
+                    lanes.insert(i, here);
+                    lanes.insert(j, there);
+                }
             }
-            Orientation::Diagonal => {
-                let x_range: Vec<usize> = if self.p0.x < self.p1.x {
-                    (x_min..=x_max).collect()
-                } else {
-                    (x_min..=x_max).rev().collect()
-                };
+        }
 
-                let y_range: Vec<usize> = if self.p0.y < self.p1.y {
-                    (y_min..=y_max).collect()
-                } else {
-                    (y_min..=y_max).rev().collect()
-                };
+        mixed
+    }
+
+    fn overlaps(&self) -> usize {
+        let mut overlaps = self.lanes.iter().map(Lane::stacked).sum::<usize>();
+        for lanes in self.mixed().values() {
+            // Counted once for each lane it is stacked on, and should be counted once.
+            let stacked = lanes.values().filter(|&&count| count >= 2).count();
+            overlaps = overlaps + 1 - stacked;
+        }
+
+        overlaps
+    }
 
-                x_range
+    /// For each orientation, the number of overlapping points that a line of that orientation
+    /// passes through.
+    fn overlaps_by_orientation(&self) -> BTreeMap<Orientation, usize> {
+        let mut overlaps = Orientation::ALL
+            .into_iter()
+            .map(|orientation| (orientation, 0))
+            .collect::<BTreeMap<Orientation, usize>>();
+        for lane in &self.lanes {
+            *overlaps.get_mut(&lane.track.orientation()).unwrap() += lane.stacked();
+        }
+        for lanes in self.mixed().values() {
+            for orientation in Orientation::ALL {
+                let counts = lanes
                     .iter()
-                    .zip(y_range.iter())
-                    .map(|(&x, &y)| Point { x, y })
-                    .collect()
+                    .filter(|&(&i, _)| self.lanes[i].track.orientation() == orientation)
+                    .map(|(_, &count)| count)
+                    .collect::<Vec<usize>>();
+                if !counts.is_empty() {
+                    let stacked = counts.iter().filter(|&&count| count >= 2).count();
+                    let overlaps = overlaps.get_mut(&orientation).unwrap();
+                    *overlaps = *overlaps + 1 - stacked;
+                }
             }
         }
+
+        overlaps
+    }
+}
+
+/// The largest heat map drawn, in points.
+const MAX_HEAT_MAP_POINTS: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeatMapTooLarge {
+    width: u64,
+    height: u64,
+}
+
+impl fmt::Display for HeatMapTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {}x{} heat map is larger than {} points",
+            self.width, self.height, MAX_HEAT_MAP_POINTS
+        )
     }
 }
 
 #[derive(Clone, Debug)]
 struct Board {
     lines: Vec<Line>,
-    rows: usize,
-    cols: usize,
 }
 
 impl Board {
-    fn point_to_index(&self, p: &Point) -> usize {
-        p.y * self.rows + p.x
+    fn coverage<F>(&self, include: F) -> Coverage
+    where
+        F: Fn(Orientation) -> bool,
+    {
+        let mut tracks: BTreeMap<Track, (Point, Vec<(i64, i64)>)> = BTreeMap::new();
+        for line in self.lines.iter().filter(|line| include(line.orientation())) {
+            let track = line.track();
+            let (origin, intervals) = tracks.entry(track).or_insert((line.p0, vec![]));
+            let (s0, s1) = (track.steps(*origin, line.p0), track.steps(*origin, line.p1));
+            intervals.push((s0.min(s1), s0.max(s1)));
+        }
+        let lanes = tracks
+            .into_iter()
+            .map(|(track, (origin, intervals))| Lane::new(track, origin, sweep(&intervals)))
+            .collect();
+
+        Coverage { lanes }
     }
 
     fn count_non_diagonal_overlaps(&self) -> usize {
-        let cells = vec![0; self.rows * self.cols];
-
-        self.lines
-            .iter()
-            .filter(|line| line.orientation() != Orientation::Diagonal)
-            .flat_map(Line::to_points)
-            .fold(cells, |mut cells, p| {
-                let index = self.point_to_index(&p);
-                cells[index] += 1;
-                cells
-            })
-            .iter()
-            .filter(|&&x| x >= 2)
-            .count()
+        self.coverage(|orientation| {
+            matches!(orientation, Orientation::Horizontal | Orientation::Vertical)
+        })
+        .overlaps()
     }
 
     fn count_overlaps(&self) -> usize {
-        let cells = vec![0; self.rows * self.cols];
+        self.coverage(|_| true).overlaps()
+    }
 
-        let cells = self
-            .lines
-            .iter()
-            .flat_map(Line::to_points)
-            .fold(cells, |mut cells, p| {
-                let index = self.point_to_index(&p);
-                cells[index] += 1;
-                cells
-            });
+    /// For each orientation, the number of overlapping points that a line of that orientation
+    /// passes through.
+    fn overlaps_by_orientation(&self) -> BTreeMap<Orientation, usize> {
+        self.coverage(|_| true).overlaps_by_orientation()
+    }
+
+    /// Draws how many lines cover each point of the bounding box, with `.` for none and `+`
+    /// for ten or more.  Boxes of more than `MAX_HEAT_MAP_POINTS` points are refused.
+    fn heat_map(&self) -> Result<String, HeatMapTooLarge> {
+        let ends = || self.lines.iter().flat_map(|line| [line.p0, line.p1]);
+        let (left, right) = match ends().map(|p| p.x).minmax() {
+            MinMax(min, max) => (min, max),
+            OneElement(x) => (x, x),
+            NoElements => return Ok(String::new()),
+        };
+        let (top, bottom) = ends().map(|p| p.y).minmax().into_option().unwrap();
+
+        let width = right.abs_diff(left) + 1;
+        let height = bottom.abs_diff(top) + 1;
+        if width.saturating_mul(height) > MAX_HEAT_MAP_POINTS {
+            return Err(HeatMapTooLarge { width, height });
+        }
+
+        // Small enough to draw, so every line is short enough to visit point by point.
+        let mut counts = vec![0; (width * height) as usize];
+        for p in self.lines.iter().flat_map(|line| line.to_points()) {
+            counts[(p.y - top) as usize * width as usize + (p.x - left) as usize] += 1;
+        }
+
+        let mut out = String::new();
+        for row in counts.chunks(width as usize) {
+            for &count in row {
+                let c = match count {
+                    0 => '.',
+                    n @ 1..=9 => char::from_digit(n as u32, 10).unwrap(),
+                    _ => '+',
+                };
+                out.push(c);
+            }
+            out.push('\n');
+        }
 
-        cells.iter().filter(|&&x| x >= 2).count()
+        Ok(out)
     }
 }
 
@@ -162,19 +479,25 @@ impl From<&str> for Board {
     fn from(s: &str) -> Self {
         let lines = s.lines().map(Line::from).collect::<Vec<Line>>();
 
-        let max =
-            lines
-                .iter()
-                .flat_map(|line| [line.p0, line.p1])
-                .fold(Point::default(), |acc, p| Point {
-                    x: acc.x.max(p.x),
-                    y: acc.y.max(p.y),
-                });
+        Self { lines }
+    }
+}
 
-        let rows = max.y + 1;
-        let cols = max.x + 1;
+/// Runs one of the commands for looking at the puzzle's vents by hand:
+/// - `orientations` counts the overlapping points that lines of each orientation pass through;
+/// - `heat-map` draws how many lines cover each point.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let input = input(DAY);
+    let board = Board::from(input.as_str());
 
-        Self { lines, rows, cols }
+    match args {
+        ["orientations"] => Ok(board
+            .overlaps_by_orientation()
+            .iter()
+            .map(|(orientation, overlaps)| format!("{:?}: {}\n", orientation, overlaps))
+            .collect()),
+        ["heat-map"] => board.heat_map().map_err(|e| e.to_string()),
+        _ => Err("usage: aoc2021 5 orientations|heat-map".to_string()),
     }
 }
 
@@ -190,7 +513,10 @@ fn p2(input: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
+    use quickcheck::QuickCheck;
 
     const INPUT: &str = indoc! {"
         0,9 -> 5,9
@@ -215,7 +541,9 @@ mod tests {
 
     #[test]
     fn p2() {
-        let points = super::Line::from("1,1 -> 3,3").to_points();
+        let points = super::Line::from("1,1 -> 3,3")
+            .to_points()
+            .collect::<Vec<_>>();
         assert_eq!(
             points,
             vec![
@@ -224,7 +552,9 @@ mod tests {
                 super::Point { x: 3, y: 3 },
             ]
         );
-        let points = super::Line::from("9,7 -> 7,9").to_points();
+        let points = super::Line::from("9,7 -> 7,9")
+            .to_points()
+            .collect::<Vec<_>>();
         assert_eq!(
             points,
             vec![
@@ -239,4 +569,204 @@ mod tests {
         let input = super::input(super::DAY);
         assert_eq!(super::p2(&input), 20196);
     }
+
+    #[test]
+    fn oblique_lines() {
+        let points = Line::from("0,0 -> 6,4").to_points().collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![
+                Point { x: 0, y: 0 },
+                Point { x: 3, y: 2 },
+                Point { x: 6, y: 4 }
+            ]
+        );
+        assert_eq!(Line::from("0,0 -> 6,4").orientation(), Orientation::Oblique);
+        assert_eq!(Line::from("5,5 -> 5,5").to_points().count(), 1);
+
+        let board = Board::from("0,0 -> 6,4\n3,0 -> 3,9\n9,6 -> 0,0\n");
+        assert_eq!(board.count_overlaps(), 3);
+    }
+
+    #[test]
+    fn huge_coordinates() {
+        // Lines with coprime extents cover only their end points.
+        let board = Board::from(indoc! {"
+            0,0 -> 3000000001,1000000000
+            3000000001,0 -> 0,1000000000
+            3000000001,1000000000 -> 7000000003,1000000001
+        "});
+        assert_eq!(board.count_overlaps(), 1);
+        assert_eq!(board.coverage(|_| true).lanes.len(), 3);
+    }
+
+    #[test]
+    fn long_axis_aligned_lines() {
+        let board = Board::from(indoc! {"
+            0,0 -> 1000000000,0
+            1500000000,0 -> 500000000,0
+            700000000,-5 -> 700000000,5
+            0,0 -> 5,5
+        "});
+        assert_eq!(board.count_non_diagonal_overlaps(), 500000001);
+        assert_eq!(board.count_overlaps(), 500000002);
+
+        let overlaps = board.overlaps_by_orientation();
+        assert_eq!(overlaps[&Orientation::Horizontal], 500000002);
+        assert_eq!(overlaps[&Orientation::Vertical], 1);
+        assert_eq!(overlaps[&Orientation::Diagonal], 1);
+
+        // The point where two stacked rows cross two stacked columns counts once.
+        let board = Board::from(indoc! {"
+            0,-10 -> 0,10
+            0,0 -> 0,20
+            -5,5 -> 5,5
+            5,5 -> -5,5
+        "});
+        assert_eq!(board.count_overlaps(), 21);
+    }
+
+    #[test]
+    fn long_diagonal_lines() {
+        let board = Board::from(indoc! {"
+            0,0 -> 1000000000,1000000000
+            2000000000,2000000000 -> 0,0
+            1000000000,0 -> 0,1000000000
+            0,1 -> 999999999,1000000000
+            500000000,0 -> 500000000,1000000000
+            0,0 -> 3000000000,2000000000
+        "});
+        assert_eq!(board.coverage(|_| true).lanes.len(), 5);
+        assert_eq!(board.count_overlaps(), 1000000003);
+
+        let overlaps = board.overlaps_by_orientation();
+        assert_eq!(overlaps[&Orientation::Diagonal], 1000000003);
+        assert_eq!(overlaps[&Orientation::Vertical], 2);
+        assert_eq!(overlaps[&Orientation::Oblique], 2);
+    }
+
+    /// The number of overlapping points, in total and for each orientation.
+    fn brute_force(board: &Board) -> (usize, BTreeMap<Orientation, usize>) {
+        let mut counts: HashMap<Point, Vec<Orientation>> = HashMap::new();
+        for line in &board.lines {
+            for p in line.to_points() {
+                counts.entry(p).or_default().push(line.orientation());
+            }
+        }
+
+        let mut by_orientation = BTreeMap::new();
+        let overlaps = counts.values().filter(|lines| lines.len() >= 2);
+        for orientation in overlaps
+            .clone()
+            .flat_map(|lines| lines.iter().sorted().dedup())
+        {
+            *by_orientation.entry(*orientation).or_default() += 1;
+        }
+
+        (overlaps.count(), by_orientation)
+    }
+
+    fn matches_brute_force(ends: Vec<(u8, u8, u8, u8)>) -> bool {
+        let lines = ends
+            .into_iter()
+            .map(|(x0, y0, x1, y1)| Line {
+                p0: Point {
+                    x: (x0 % 12) as i64,
+                    y: (y0 % 12) as i64,
+                },
+                p1: Point {
+                    x: (x1 % 12) as i64,
+                    y: (y1 % 12) as i64,
+                },
+            })
+            .collect();
+        let board = Board { lines };
+
+        let (overlaps, by_orientation) = brute_force(&board);
+        let mut actual = board.overlaps_by_orientation();
+        actual.retain(|_, &mut count| count > 0);
+
+        board.count_overlaps() == overlaps && actual == by_orientation
+    }
+
+    #[test]
+    fn sweep_matches_brute_force() {
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(matches_brute_force as fn(Vec<(u8, u8, u8, u8)>) -> bool);
+
+        assert_eq!(
+            sweep(&[(0, 4), (2, 6), (2, 3)]),
+            vec![
+                Run {
+                    start: 0,
+                    end: 1,
+                    count: 1
+                },
+                Run {
+                    start: 2,
+                    end: 3,
+                    count: 3
+                },
+                Run {
+                    start: 4,
+                    end: 4,
+                    count: 2
+                },
+                Run {
+                    start: 5,
+                    end: 6,
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn overlaps_by_orientation() {
+        let board = Board::from(INPUT);
+        let overlaps = board.overlaps_by_orientation();
+        assert_eq!(overlaps[&Orientation::Horizontal], 7);
+        assert_eq!(overlaps[&Orientation::Vertical], 4);
+        assert_eq!(overlaps[&Orientation::Diagonal], 7);
+        assert_eq!(overlaps[&Orientation::Oblique], 0);
+    }
+
+    #[test]
+    fn tool() {
+        let orientations = super::tool(&["orientations"]).unwrap();
+        assert_eq!(orientations.lines().count(), 4);
+        assert!(orientations.starts_with("Horizontal: "));
+        assert!(orientations.ends_with("Oblique: 0\n"));
+
+        let heat_map = super::tool(&["heat-map"]).unwrap();
+        assert!(heat_map.chars().all(|c| ".123456789+\n".contains(c)));
+
+        assert!(super::tool(&["heat"]).is_err());
+    }
+
+    #[test]
+    fn heat_map() {
+        assert_eq!(
+            Board::from(INPUT).heat_map().unwrap(),
+            indoc! {"
+                1.1....11.
+                .111...2..
+                ..2.1.111.
+                ...1.2.2..
+                .112313211
+                ...1.2....
+                ..1...1...
+                .1.....1..
+                1.......1.
+                222111....
+            "}
+        );
+
+        let board = Board::from("0,0 -> 1000000000,0\n0,0 -> 0,2\n");
+        assert_eq!(
+            board.heat_map().unwrap_err().to_string(),
+            "a 1000000001x3 heat map is larger than 1048576 points"
+        );
+    }
 }
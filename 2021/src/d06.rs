@@ -1,43 +1,72 @@
 use crate::input;
+use crate::matrix::Matrix;
+use crate::output::Solution;
+
+use num_bigint::BigUint;
 
 const DAY: usize = 6;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
+}
+
+/// The timer a fish is reset to after spawning, and the timer a newborn fish starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Timers {
+    reset: usize,
+    spawn: usize,
+}
+
+impl Timers {
+    const DEFAULT: Self = Self { reset: 6, spawn: 8 };
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct State {
-    fish: [usize; 9],
+    fish: Vec<usize>,
+    timers: Timers,
 }
 
 impl From<&str> for State {
     fn from(s: &str) -> Self {
-        let fish = s
+        Self::new(s, Timers::DEFAULT)
+    }
+}
+
+impl State {
+    fn new(s: &str, timers: Timers) -> Self {
+        let ages = s
             .trim()
-            .split(",")
+            .split(',')
             .map(str::parse::<usize>)
             .filter_map(Result::ok)
-            .fold([0; 9], |mut acc, age| {
-                acc[age] += 1;
-                acc
-            });
-
-        Self { fish }
+            .collect::<Vec<usize>>();
+
+        let width = ages
+            .iter()
+            .copied()
+            .chain([timers.reset, timers.spawn])
+            .max()
+            .unwrap()
+            + 1;
+        let fish = ages.into_iter().fold(vec![0; width], |mut acc, age| {
+            acc[age] += 1;
+            acc
+        });
+
+        Self { fish, timers }
     }
-}
 
-impl State {
     fn next(mut self) -> Self {
         let zero = self.fish[0];
         self.fish.rotate_left(1);
-        self.fish[6] += zero;
+        *self.fish.last_mut().unwrap() = 0;
+        self.fish[self.timers.reset] += zero;
+        self.fish[self.timers.spawn] += zero;
         self
     }
 
@@ -52,16 +81,49 @@ fn simulate(initial_state: State, days: usize) -> usize {
         .count_fish()
 }
 
+/// Advances a population any number of days at once by raising its one-day transition to a
+/// power.
+struct Recurrence {
+    transition: Matrix,
+}
+
+impl Recurrence {
+    fn new(timers: Timers, width: usize) -> Self {
+        let mut transition = Matrix::zero(width);
+        for i in 0..width - 1 {
+            transition.increment(i, i + 1);
+        }
+        transition.increment(timers.reset, 0);
+        transition.increment(timers.spawn, 0);
+
+        Self { transition }
+    }
+
+    fn count_fish(&self, state: &State, days: u64) -> BigUint {
+        self.transition
+            .pow(days)
+            .apply(&state.fish)
+            .into_iter()
+            .sum()
+    }
+}
+
+fn jump(state: &State, days: u64) -> BigUint {
+    Recurrence::new(state.timers, state.fish.len()).count_fish(state, days)
+}
+
 fn p1(input: &str) -> usize {
     simulate(State::from(input), 80)
 }
 
-fn p2(input: &str) -> usize {
-    simulate(State::from(input), 256)
+fn p2(input: &str) -> BigUint {
+    jump(&State::from(input), 256)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -78,9 +140,45 @@ mod tests {
 
     #[test]
     fn p2() {
-        assert_eq!(super::p2(INPUT), 26984457539);
+        assert_eq!(super::p2(INPUT), BigUint::from(26984457539u64));
 
         let input = super::input(super::DAY);
-        assert_eq!(super::p2(&input), 1631647919273);
+        assert_eq!(super::p2(&input), BigUint::from(1631647919273u64));
+    }
+
+    #[test]
+    fn jump_matches_simulation() {
+        let timers = [
+            Timers::DEFAULT,
+            Timers { reset: 2, spawn: 4 },
+            Timers { reset: 5, spawn: 3 },
+            Timers { reset: 0, spawn: 1 },
+        ];
+        for timers in timers {
+            for s in [INPUT, "0", "9,12,0,0"] {
+                let state = State::new(s, timers);
+                let recurrence = Recurrence::new(state.timers, state.fish.len());
+                for days in 0..60 {
+                    assert_eq!(
+                        recurrence.count_fish(&state, days),
+                        BigUint::from(simulate(state.clone(), days as usize)),
+                        "{:?} from {} after {} days",
+                        timers,
+                        s.trim(),
+                        days
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jump_far() {
+        // Each fish alive on day n either was alive 7 days earlier or spawned from one alive 9
+        // days earlier.
+        let state = State::from(INPUT);
+        let count = |days| jump(&state, days);
+        let n = 100_000;
+        assert_eq!(count(n), count(n - 7) + count(n - 9));
     }
 }
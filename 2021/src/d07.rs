@@ -1,17 +1,34 @@
 use crate::input;
+use crate::output::Solution;
 
 use tap::prelude::*;
 
 const DAY: usize = 7;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
+}
+
+/// Runs the `optimise <cost>` command, which finds the cheapest position to align the crabs
+/// to when each move costs one of:
+///
+/// * `linear`: the distance, as in part one
+/// * `triangular`: the sum of 1 up to the distance, as in part two
+/// * `quadratic`: the square of the distance plus three times the distance
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let cost_fn = match args {
+        ["optimise", "linear"] => p1_cost,
+        ["optimise", "triangular"] => p2_cost,
+        ["optimise", "quadratic"] => quadratic_cost,
+        _ => return Err("usage: aoc2021 7 optimise linear|triangular|quadratic".to_string()),
+    };
+    let (to, cost) = Positions::from(input(DAY).as_str()).optimise(cost_fn);
+
+    Ok(format!("position {} costs {}\n", to, cost))
 }
 
 struct Positions(Vec<usize>);
@@ -38,14 +55,63 @@ impl Positions {
         self.0.iter().map(cost_fn).sum()
     }
 
+    fn cost_to<F>(&self, to: usize, cost_fn: F) -> usize
+    where
+        F: Fn(usize, usize) -> usize,
+    {
+        self.cost(|&from| cost_fn(from, to))
+    }
+
+    fn range(&self) -> (usize, usize) {
+        (*self.0.first().unwrap(), *self.0.last().unwrap())
+    }
+
+    /// Finds the cheapest position to align to and its cost.
+    ///
+    /// The cost of moving a crab from `from` to `to` must be convex in `to`, which makes the
+    /// total convex as well.  The minimum is then where the total stops decreasing, found by a
+    /// binary search over the slope between neighbouring positions.
+    fn optimise<F>(&self, cost_fn: F) -> (usize, usize)
+    where
+        F: Fn(usize, usize) -> usize,
+    {
+        let (mut lo, mut hi) = self.range();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.cost_to(mid, &cost_fn) <= self.cost_to(mid + 1, &cost_fn) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        (lo, self.cost_to(lo, cost_fn))
+    }
+
+    /// The optimum for `p1_cost`.
     fn median(&self) -> usize {
         let index = self.0.len() / 2;
         self.0[index]
     }
+
+    /// The optimum for `p2_cost`.
+    ///
+    /// The real-valued optimum lies within 1/2 of the mean, so the integer optimum is one of
+    /// the few positions from `floor(mean - 1/2)` to `ceil(mean + 1/2)`.
+    fn around_mean(&self) -> usize {
+        let n = self.0.len();
+        let sum: usize = self.0.iter().sum();
+        let lo = (2 * sum).saturating_sub(n) / (2 * n);
+        let hi = (2 * sum + n).div_ceil(2 * n);
+
+        (lo..=hi)
+            .min_by_key(|&to| self.cost_to(to, p2_cost))
+            .unwrap()
+    }
 }
 
 fn p1_cost(a: usize, b: usize) -> usize {
-    ((a as i32) - (b as i32)).abs() as usize
+    ((a as i32) - (b as i32)).unsigned_abs() as usize
 }
 
 fn p1(input: &str) -> usize {
@@ -59,21 +125,24 @@ fn p2_cost(a: usize, b: usize) -> usize {
     (diff * (diff + 1)) / 2
 }
 
+fn quadratic_cost(a: usize, b: usize) -> usize {
+    let diff = p1_cost(a, b);
+    diff * diff + 3 * diff
+}
+
 fn p2(input: &str) -> usize {
     let positions = Positions::from(input);
-
-    let min = *positions.0.first().unwrap();
-    let max = *positions.0.last().unwrap();
-
-    (min..=max)
-        .map(|to| positions.cost(|&from| p2_cost(from, to)))
-        .min()
-        .unwrap()
+    let to = positions.around_mean();
+    positions.cost_to(to, p2_cost)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
+    use itertools::Itertools;
+    use quickcheck::{QuickCheck, TestResult};
 
     const INPUT: &str = indoc! {"
         16,1,2,0,4,2,7,1,2,14
@@ -94,4 +163,50 @@ mod tests {
         let input = super::input(super::DAY);
         assert_eq!(super::p2(&input), 97164301);
     }
+
+    fn brute_force<F>(positions: &Positions, cost_fn: F) -> usize
+    where
+        F: Fn(usize, usize) -> usize,
+    {
+        let (min, max) = positions.range();
+        (min..=max)
+            .map(|to| positions.cost_to(to, &cost_fn))
+            .min()
+            .unwrap()
+    }
+
+    fn matches_brute_force(positions: Vec<u8>) -> TestResult {
+        if positions.is_empty() {
+            return TestResult::discard();
+        }
+        let positions = Positions::from(positions.iter().map(u8::to_string).join(",").as_str());
+
+        let linear = brute_force(&positions, p1_cost);
+        let triangular = brute_force(&positions, p2_cost);
+        let quadratic = brute_force(&positions, quadratic_cost);
+        TestResult::from_bool(
+            positions.optimise(p1_cost).1 == linear
+                && positions.optimise(p2_cost).1 == triangular
+                && positions.optimise(quadratic_cost).1 == quadratic
+                && positions.cost_to(positions.median(), p1_cost) == linear
+                && positions.cost_to(positions.around_mean(), p2_cost) == triangular,
+        )
+    }
+
+    #[test]
+    fn optimise() {
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(matches_brute_force as fn(Vec<u8>) -> TestResult);
+
+        let positions = Positions::from(INPUT);
+        assert_eq!(positions.optimise(p1_cost), (2, 37));
+        assert_eq!(positions.optimise(p2_cost), (5, 168));
+
+        assert_eq!(
+            super::tool(&["optimise", "triangular"]),
+            Ok("position 465 costs 97164301\n".to_string())
+        );
+        assert!(super::tool(&["optimise", "cubic"]).is_err());
+    }
 }
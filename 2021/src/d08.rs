@@ -1,38 +1,62 @@
 use crate::input;
+use crate::output::Solution;
 
-use std::ops::BitAnd;
+use itertools::Itertools;
+
+use std::fmt;
 
 const DAY: usize = 8;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
+    let input = input(DAY);
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::try_time(DAY, 2, || p2(&input)),
+    ]
+}
+
+/// Runs the `decode decimal|hex [<entry>]` command, which reads the output of an entry such
+/// as `"cf fca acdfgeb | cf acf"` on a display of that alphabet, or of every entry in the puzzle
+/// input if none is given.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let (symbols, entry) = match args {
+        ["decode", "decimal", entry @ ..] if entry.len() <= 1 => (&DECIMAL[..], entry.first()),
+        ["decode", "hex", entry @ ..] if entry.len() <= 1 => (&HEX[..], entry.first()),
+        _ => return Err("usage: aoc2021 8 decode decimal|hex [ENTRY]".to_string()),
+    };
+    let alphabet = Alphabet::from(symbols);
+
     let input = input(DAY);
+    let lines = match entry {
+        Some(entry) => vec![*entry],
+        None => input.lines().collect(),
+    };
     let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    for line in lines {
+        let valid = line.chars().all(|c| matches!(c, 'a'..='g' | ' ' | '|'));
+        if !valid || line.matches(" | ").count() != 1 {
+            return Err(format!("malformed entry '{}'", line));
+        }
+        match Entry::from(line).output_value(&alphabet) {
+            Ok(value) if symbols.len() == 16 => output += &format!("{:X}\n", value),
+            Ok(value) => output += &format!("{}\n", value),
+            Err(error) => output += &format!("error: {}\n", error),
+        }
+    }
+
+    Ok(output)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 struct Pattern {
     segments: u8,
     active: u8,
 }
 
-impl Default for Pattern {
-    fn default() -> Self {
-        Self {
-            segments: 0,
-            active: 0,
-        }
-    }
-}
-
 impl From<&str> for Pattern {
     fn from(s: &str) -> Self {
         s.chars()
-            .map(|c| c as u8 - 'a' as u8)
+            .map(|c| c as u8 - b'a')
             .fold(Pattern::default(), |mut acc, index| {
                 acc.segments |= 1 << index;
                 acc.active += 1;
@@ -41,63 +65,169 @@ impl From<&str> for Pattern {
     }
 }
 
-impl BitAnd for Pattern {
-    type Output = Self;
-
-    fn bitand(self, rhs: Self) -> Self::Output {
-        let segments = self.segments & rhs.segments;
-        let active = segments.count_ones() as u8;
-        Self { segments, active }
-    }
-}
-
-// digit active_segments
-// 0     6
-// 1     2
-// 2     5
-// 3     5
-// 4     4
-// 5     5
-// 6     6
-// 7     3
-// 8     7
-// 9     6
-//
-// active_segments possible_digits
-// 2               1
-// 3               7
-// 4               4
-// 5               2, 3, 5
-// 6               0, 6, 9
-// 7               8
-impl Pattern {
-    fn decode(&self, one: Pattern, four: Pattern) -> usize {
-        match self.active {
-            2 => 1,
-            3 => 7,
-            4 => 4,
-            5 => {
-                if *self & one == one {
-                    3
-                } else {
-                    match (*self & four).active {
-                        2 => 2,
-                        3 => 5,
-                        _ => unreachable!(),
-                    }
-                }
-            }
-            6 => {
-                if (*self & one).active == 1 {
-                    6
-                } else if *self & four == four {
-                    9
-                } else {
-                    0
-                }
-            }
-            7 => 8,
-            _ => unreachable!(),
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (0..SEGMENTS)
+            .filter(|&i| self.segments & (1 << i) != 0)
+            .try_for_each(|i| write!(f, "{}", (b'a' + i as u8) as char))
+    }
+}
+
+const SEGMENTS: usize = 7;
+
+//  aaaa
+// b    c
+// b    c
+//  dddd
+// e    f
+// e    f
+//  gggg
+const DECIMAL: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+const HEX: [&str; 16] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+    "abcdef", "bdefg", "abeg", "cdefg", "abdeg", "abde",
+];
+
+/// The segments lit for each symbol of a display, indexed by the symbol's value.
+#[derive(Debug, Clone)]
+struct Alphabet {
+    symbols: Vec<Pattern>,
+}
+
+impl From<&[&str]> for Alphabet {
+    fn from(symbols: &[&str]) -> Self {
+        Self {
+            symbols: symbols.iter().copied().map(Pattern::from).collect(),
+        }
+    }
+}
+
+impl Alphabet {
+    fn value_of(&self, pattern: Pattern) -> Option<usize> {
+        self.symbols.iter().position(|&symbol| symbol == pattern)
+    }
+
+    /// Whether some symbol agrees with `pattern` on every wire assigned so far.
+    fn admits(&self, pattern: Pattern, wiring: &[u8]) -> bool {
+        let (observed, image) =
+            wiring
+                .iter()
+                .enumerate()
+                .fold((0, 0), |(observed, image), (wire, &segment)| {
+                    let lit = pattern.segments & (1 << wire) != 0;
+                    (observed | (lit as u8) << segment, image | 1 << segment)
+                });
+
+        self.symbols
+            .iter()
+            .any(|symbol| symbol.active == pattern.active && symbol.segments & image == observed)
+    }
+}
+
+/// Maps each observed wire, by index, to the segment it actually drives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Wiring(Vec<u8>);
+
+impl Wiring {
+    fn apply(&self, pattern: Pattern) -> Pattern {
+        let segments = self
+            .0
+            .iter()
+            .enumerate()
+            .filter(|&(wire, _)| pattern.segments & (1 << wire) != 0)
+            .fold(0, |acc, (_, &segment)| acc | 1 << segment);
+
+        Pattern {
+            segments,
+            ..pattern
+        }
+    }
+
+    fn solve(alphabet: &Alphabet, patterns: &[Pattern]) -> Result<Self, SolveError> {
+        let mut solutions = Vec::new();
+        search(alphabet, patterns, &mut Vec::new(), &mut solutions);
+
+        match solutions.len() {
+            0 => Err(SolveError::NoWiring {
+                inconsistent: inconsistent_patterns(alphabet, patterns),
+            }),
+            1 => Ok(solutions.pop().unwrap()),
+            n => Err(SolveError::Ambiguous(n)),
+        }
+    }
+}
+
+fn search(
+    alphabet: &Alphabet,
+    patterns: &[Pattern],
+    wiring: &mut Vec<u8>,
+    solutions: &mut Vec<Wiring>,
+) {
+    if wiring.len() == SEGMENTS {
+        solutions.push(Wiring(wiring.clone()));
+        return;
+    }
+
+    for segment in 0..SEGMENTS as u8 {
+        if wiring.contains(&segment) {
+            continue;
+        }
+        wiring.push(segment);
+        if patterns
+            .iter()
+            .all(|&pattern| alphabet.admits(pattern, wiring))
+        {
+            search(alphabet, patterns, wiring, solutions);
+        }
+        wiring.pop();
+    }
+}
+
+/// The patterns left unexplained by whichever wiring explains the most of them.
+fn inconsistent_patterns(alphabet: &Alphabet, patterns: &[Pattern]) -> Vec<Pattern> {
+    (0..SEGMENTS as u8)
+        .permutations(SEGMENTS)
+        .map(|wiring| {
+            patterns
+                .iter()
+                .copied()
+                .filter(|&pattern| alphabet.admits(pattern, &wiring))
+                .count()
+        })
+        .max()
+        .and_then(|best| {
+            (0..SEGMENTS as u8)
+                .permutations(SEGMENTS)
+                .find_map(|wiring| {
+                    let unexplained = patterns
+                        .iter()
+                        .copied()
+                        .filter(|&pattern| !alphabet.admits(pattern, &wiring))
+                        .collect::<Vec<Pattern>>();
+                    (patterns.len() - unexplained.len() == best).then_some(unexplained)
+                })
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SolveError {
+    NoWiring { inconsistent: Vec<Pattern> },
+    Ambiguous(usize),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoWiring { inconsistent } => write!(
+                f,
+                "no wiring fits every pattern; inconsistent: {}",
+                inconsistent.iter().join(", ")
+            ),
+            Self::Ambiguous(n) => write!(f, "{} wirings fit every pattern", n),
         }
     }
 }
@@ -112,7 +242,7 @@ impl From<&str> for Entry {
     fn from(s: &str) -> Self {
         let mut halves = s
             .split(" | ")
-            .map(|half| half.split(" ").map(Pattern::from).collect::<Vec<Pattern>>());
+            .map(|half| half.split(' ').map(Pattern::from).collect::<Vec<Pattern>>());
 
         Self {
             patterns: halves.next().unwrap(),
@@ -122,25 +252,22 @@ impl From<&str> for Entry {
 }
 
 impl Entry {
-    fn output_value(&self) -> usize {
-        let one = self
+    /// Reads the output in the alphabet's base.  Output patterns count as observations too.
+    fn output_value(&self, alphabet: &Alphabet) -> Result<usize, SolveError> {
+        let observed = self
             .patterns
             .iter()
-            .find(|&&segments| segments.active == 2)
-            .unwrap();
+            .chain(&self.output)
+            .copied()
+            .unique()
+            .collect::<Vec<Pattern>>();
+        let wiring = Wiring::solve(alphabet, &observed)?;
 
-        let four = self
-            .patterns
+        Ok(self
+            .output
             .iter()
-            .find(|&&segments| segments.active == 4)
-            .unwrap();
-
-        self.output
-            .iter()
-            .map(|segments| segments.decode(*one, *four))
-            .enumerate()
-            .map(|(i, digit)| digit * 10_usize.pow(3 - i as u32))
-            .sum()
+            .map(|&pattern| alphabet.value_of(wiring.apply(pattern)).unwrap())
+            .fold(0, |acc, digit| acc * alphabet.symbols.len() + digit))
     }
 }
 
@@ -155,14 +282,20 @@ fn p1(input: &str) -> usize {
         .count()
 }
 
-fn p2(input: &str) -> usize {
+fn p2(input: &str) -> Result<usize, SolveError> {
     let entries = input.lines().map(Entry::from).collect::<Vec<Entry>>();
 
-    entries.iter().map(Entry::output_value).sum()
+    let alphabet = Alphabet::from(&DECIMAL[..]);
+    entries
+        .iter()
+        .map(|entry| entry.output_value(&alphabet))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     use indoc::indoc;
 
     const INPUT: &str = indoc! {"
@@ -188,9 +321,95 @@ mod tests {
 
     #[test]
     fn p2() {
-        assert_eq!(super::p2(INPUT), 61229);
+        assert_eq!(super::p2(INPUT), Ok(61229));
 
         let input = super::input(super::DAY);
-        assert_eq!(super::p2(&input), 1070188);
+        assert_eq!(super::p2(&input), Ok(1070188));
+    }
+
+    /// Rewires every symbol of the alphabet by sending segment `i` to wire `shuffle[i]`.
+    fn scramble(alphabet: &[&str], shuffle: &str) -> Vec<String> {
+        alphabet
+            .iter()
+            .map(|symbol| {
+                symbol
+                    .bytes()
+                    .map(|c| shuffle.as_bytes()[(c - b'a') as usize] as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn solve() {
+        let decimal = Alphabet::from(&DECIMAL[..]);
+        let entry = Entry::from(INPUT.lines().next().unwrap());
+        assert_eq!(entry.output_value(&decimal), Ok(8394));
+
+        let hex = Alphabet::from(&HEX[..]);
+        let scrambled = scramble(&HEX, "gfedcba");
+        let line = format!(
+            "{} | {} {} {} {}",
+            scrambled.join(" "),
+            scrambled[0xC],
+            scrambled[0xA],
+            scrambled[0xF],
+            scrambled[0xE]
+        );
+        assert_eq!(Entry::from(line.as_str()).output_value(&hex), Ok(0xCAFE));
+    }
+
+    #[test]
+    fn tool() {
+        let scrambled = scramble(&HEX, "gfedcba");
+        let entry = format!(
+            "{} | {} {}",
+            scrambled.join(" "),
+            scrambled[0xB],
+            scrambled[0xE]
+        );
+        assert_eq!(
+            super::tool(&["decode", "hex", &entry]),
+            Ok("BE\n".to_string())
+        );
+        assert_eq!(
+            super::tool(&["decode", "decimal", "cf abcdefg | cf"]),
+            Ok("error: 240 wirings fit every pattern\n".to_string())
+        );
+        assert!(super::tool(&["decode", "decimal", "cf abcdefg"]).is_err());
+        assert!(super::tool(&["decode", "octal"]).is_err());
+    }
+
+    #[test]
+    fn solve_errors() {
+        let decimal = Alphabet::from(&DECIMAL[..]);
+
+        let one_and_eight = ["cf", "abcdefg"].map(Pattern::from);
+        assert_eq!(
+            Wiring::solve(&decimal, &one_and_eight),
+            Err(SolveError::Ambiguous(240))
+        );
+        assert_eq!(
+            super::p2("cf abcdefg | cf\n"),
+            Err(SolveError::Ambiguous(240))
+        );
+
+        let mut patterns = scramble(&DECIMAL, "cdefgab");
+        patterns[2] = "abcdg".to_string();
+        let patterns = patterns
+            .iter()
+            .map(|s| Pattern::from(s.as_str()))
+            .collect::<Vec<_>>();
+        let error = Wiring::solve(&decimal, &patterns).unwrap_err();
+        assert_eq!(
+            error,
+            SolveError::NoWiring {
+                inconsistent: vec![Pattern::from("abcdg")]
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "no wiring fits every pattern; inconsistent: abcdg"
+        );
     }
 }
@@ -1,18 +1,16 @@
 use crate::input;
+use crate::output::Solution;
 
-use std::collections::HashSet;
 use tap::prelude::*;
 
 const DAY: usize = 9;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
 #[derive(Debug, PartialEq)]
@@ -85,32 +83,203 @@ impl Map {
         [left, right, above, below]
     }
 
-    fn basin_size(&self, index: usize) -> usize {
-        let mut visited: HashSet<usize> = HashSet::new();
-
-        let mut to_visit = vec![index];
-
-        loop {
-            let index = to_visit.pop().unwrap();
+    /// Labels every cell below 9 with the ID of its basin in one row-major scan.
+    ///
+    /// Each cell joins the basins of its left and upper neighbours, merging them with a
+    /// union-find when they differ.  IDs are numbered by first appearance.
+    fn basins(&self) -> Basins {
+        let mut parents: Vec<usize> = Vec::new();
+        let mut stats: Vec<BasinStats> = Vec::new();
+        let mut labels: Vec<Option<usize>> = vec![None; self.values.len()];
+
+        for (index, &height) in self.values.iter().enumerate() {
+            if height == 9 {
+                continue;
+            }
 
-            visited.insert(index);
+            let [left, _, above, _] = self.neighbors(index);
+            let joined = [left, above]
+                .into_iter()
+                .flatten()
+                .filter_map(|(i, _)| labels[i])
+                .map(|label| find(&mut parents, label))
+                .collect::<Vec<usize>>();
 
-            let mut neighbors = self
+            let label = match joined.as_slice() {
+                [] => {
+                    parents.push(parents.len());
+                    stats.push(BasinStats {
+                        size: 0,
+                        min: (index, height),
+                        perimeter: 0,
+                    });
+                    parents.len() - 1
+                }
+                [label] => *label,
+                &[a, b] => {
+                    if a != b {
+                        let (keep, merged) = (a.min(b), a.max(b));
+                        parents[merged] = keep;
+                        let merged = stats[merged];
+                        stats[keep].merge(&merged);
+                    }
+                    a.min(b)
+                }
+                _ => unreachable!(),
+            };
+
+            let open_sides = self
                 .neighbors(index)
                 .iter()
-                .filter_map(|x| x.as_ref())
-                .filter(|(i, v)| *i != index && *v < 9 && !visited.contains(i))
-                .map(|(i, _v)| *i)
-                .collect::<Vec<usize>>();
+                .flatten()
+                .filter(|(_, v)| *v < 9)
+                .count();
+            stats[label].merge(&BasinStats {
+                size: 1,
+                min: (index, height),
+                perimeter: 4 - open_sides,
+            });
+            labels[index] = Some(label);
+        }
+
+        let mut ids: Vec<Option<usize>> = vec![None; parents.len()];
+        let mut basins: Vec<BasinStats> = Vec::new();
+        let labels = labels
+            .into_iter()
+            .map(|label| {
+                label.map(|label| {
+                    let root = find(&mut parents, label);
+                    *ids[root].get_or_insert_with(|| {
+                        basins.push(stats[root]);
+                        basins.len() - 1
+                    })
+                })
+            })
+            .collect();
+
+        Basins {
+            cols: self.cols,
+            labels,
+            stats: basins,
+        }
+    }
+}
 
-            to_visit.append(&mut neighbors);
+fn find(parents: &mut [usize], label: usize) -> usize {
+    let parent = parents[label];
+    if parent == label {
+        label
+    } else {
+        let root = find(parents, parent);
+        parents[label] = root;
+        root
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BasinStats {
+    size: usize,
+    /// The index and height of the lowest cell.
+    min: (usize, u8),
+    /// The number of cell edges facing a 9 or the edge of the map.
+    perimeter: usize,
+}
+
+impl BasinStats {
+    fn merge(&mut self, other: &Self) {
+        self.size += other.size;
+        if (other.min.1, other.min.0) < (self.min.1, self.min.0) {
+            self.min = other.min;
+        }
+        self.perimeter += other.perimeter;
+    }
+}
+
+/// The basin ID of each cell, `None` for 9s, and the statistics of each basin by ID.
+#[derive(Debug)]
+struct Basins {
+    cols: usize,
+    labels: Vec<Option<usize>>,
+    stats: Vec<BasinStats>,
+}
+
+impl Basins {
+    fn colour(label: Option<usize>) -> (u8, u8, u8) {
+        let label = match label {
+            Some(label) => label,
+            None => return (0, 0, 0),
+        };
+
+        // Spread hues by the golden angle so that neighbouring IDs look different.
+        let hue = (label as f64 * 0.618_033_988_75).fract() * 6.0;
+        let x = 1.0 - (hue % 2.0 - 1.0).abs();
+        let (r, g, b) = match hue as usize {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        };
+        let scale = |c: f64| (64.0 + c * 191.0) as u8;
+
+        (scale(r), scale(g), scale(b))
+    }
 
-            if to_visit.len() == 0 {
-                break;
+    /// Renders the heights with each basin in its own colour using ANSI escape codes.
+    fn render_terminal(&self, map: &Map) -> String {
+        let mut out = String::new();
+        for (row, labels) in self.labels.chunks(self.cols).enumerate() {
+            for (col, &label) in labels.iter().enumerate() {
+                let height = map.values[row * self.cols + col];
+                match label {
+                    Some(_) => {
+                        let (r, g, b) = Self::colour(label);
+                        out += &format!("\x1b[38;2;{};{};{}m{}", r, g, b, height);
+                    }
+                    None => out += &format!("\x1b[2m{}", height),
+                }
+                out += "\x1b[0m";
             }
+            out.push('\n');
         }
 
-        visited.len()
+        out
+    }
+
+    /// Renders one pixel per cell as a plain PPM image, with 9s in black.
+    fn to_ppm(&self) -> String {
+        let rows = self.labels.len() / self.cols;
+        let mut out = format!("P3\n{} {}\n255\n", self.cols, rows);
+        for labels in self.labels.chunks(self.cols) {
+            let line = labels
+                .iter()
+                .map(|&label| {
+                    let (r, g, b) = Self::colour(label);
+                    format!("{} {} {}", r, g, b)
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+            out += &line;
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Runs one of the basin rendering commands on the puzzle input:
+/// - `render` colours each basin with ANSI escape codes for the terminal;
+/// - `ppm` writes the basins as a plain PPM image, to be redirected to a file.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let input = input(DAY);
+    let map = Map::from(input.as_str());
+    let basins = map.basins();
+
+    match args {
+        ["render"] => Ok(basins.render_terminal(&map)),
+        ["ppm"] => Ok(basins.to_ppm()),
+        _ => Err("usage: aoc2021 9 render|ppm".to_string()),
     }
 }
 
@@ -125,9 +294,10 @@ fn p1(input: &str) -> usize {
 fn p2(input: &str) -> usize {
     let map = Map::from(input);
 
-    map.minima()
+    map.basins()
+        .stats
         .iter()
-        .map(|(i, _v)| map.basin_size(*i))
+        .map(|basin| basin.size)
         .collect::<Vec<usize>>()
         .tap_mut(|sizes| sizes.sort())
         .iter()
@@ -165,4 +335,81 @@ mod tests {
         let input = input(super::DAY);
         assert_eq!(super::p2(&input), 1269555);
     }
+
+    #[test]
+    fn basins() {
+        let map = Map::from(INPUT);
+        let basins = map.basins();
+
+        let stats = |size, min, perimeter| BasinStats {
+            size,
+            min,
+            perimeter,
+        };
+        assert_eq!(
+            basins.stats,
+            vec![
+                stats(3, (1, 1), 8),
+                stats(9, (9, 0), 18),
+                stats(14, (22, 5), 20),
+                stats(9, (46, 5), 16),
+            ]
+        );
+        assert_eq!(basins.labels[0], Some(0));
+        assert_eq!(basins.labels[2], None);
+        assert_eq!(basins.labels[49], Some(3));
+
+        let minima = map.minima();
+        let mut lowest = basins
+            .stats
+            .iter()
+            .map(|basin| basin.min)
+            .collect::<Vec<_>>();
+        lowest.sort_unstable();
+        assert_eq!(lowest, minima);
+    }
+
+    #[test]
+    fn basins_merge() {
+        // The two arms only meet on the last row, after both have been given IDs.
+        let map = Map::from("191\n191\n111\n");
+        let basins = map.basins();
+        assert_eq!(basins.stats.len(), 1);
+        assert_eq!(basins.stats[0].size, 7);
+        assert_eq!(
+            basins.labels,
+            vec![
+                Some(0),
+                None,
+                Some(0),
+                Some(0),
+                None,
+                Some(0),
+                Some(0),
+                Some(0),
+                Some(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn to_ppm() {
+        let ppm = Map::from("19\n99\n").basins().to_ppm();
+        assert_eq!(ppm, "P3\n2 2\n255\n255 64 64 0 0 0\n0 0 0 0 0 0\n");
+    }
+
+    #[test]
+    fn tool() {
+        let rows = input(super::DAY).lines().count();
+
+        let render = super::tool(&["render"]).unwrap();
+        assert_eq!(render.lines().count(), rows);
+        assert!(render.starts_with("\x1b["));
+
+        let ppm = super::tool(&["ppm"]).unwrap();
+        assert!(ppm.starts_with("P3\n"));
+        assert_eq!(ppm.lines().count(), rows + 3);
+
+        assert!(super::tool(&["png"]).is_err());
+    }
 }
@@ -1,167 +1,197 @@
 use crate::input;
+use crate::output::Solution;
 
+use std::fmt;
 use tap::prelude::*;
 
 const DAY: usize = 10;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Delim {
-    kind: DelimKind,
-    side: Side,
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
+/// A pair of delimiters with the score for finding `close` where it does not belong and its
+/// per-character score in a completion.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum DelimKind {
-    Paren,
-    Bracket,
-    Brace,
-    Angle,
+struct Pair {
+    open: char,
+    close: char,
+    error_score: usize,
+    completion_score: usize,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Side {
-    Left,
-    Right,
+#[derive(Debug, Clone)]
+struct Config {
+    pairs: Vec<Pair>,
+    /// Each completion character multiplies the running completion score by this.
+    completion_base: usize,
 }
 
-use DelimKind::*;
-use Side::*;
-
-impl From<char> for Delim {
-    fn from(c: char) -> Self {
-        match c {
-            '(' => Delim {
-                kind: Paren,
-                side: Left,
-            },
-            ')' => Delim {
-                kind: Paren,
-                side: Right,
-            },
-            '[' => Delim {
-                kind: Bracket,
-                side: Left,
-            },
-            ']' => Delim {
-                kind: Bracket,
-                side: Right,
-            },
-            '{' => Delim {
-                kind: Brace,
-                side: Left,
-            },
-            '}' => Delim {
-                kind: Brace,
-                side: Right,
-            },
-            '<' => Delim {
-                kind: Angle,
-                side: Left,
-            },
-            '>' => Delim {
-                kind: Angle,
-                side: Right,
-            },
-            _ => unreachable!(),
+impl Default for Config {
+    fn default() -> Self {
+        let pair = |open, close, error_score, completion_score| Pair {
+            open,
+            close,
+            error_score,
+            completion_score,
+        };
+
+        Self {
+            pairs: vec![
+                pair('(', ')', 3, 1),
+                pair('[', ']', 57, 2),
+                pair('{', '}', 1197, 3),
+                pair('<', '>', 25137, 4),
+            ],
+            completion_base: 5,
         }
     }
 }
 
-impl Delim {
-    fn is_match(&self, other: Option<&Delim>) -> bool {
-        if self.side == Left {
-            true
-        } else if let Some(other) = other {
-            self.kind == other.kind && other.side == Left && self.side == Right
-        } else {
-            unreachable!()
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Diagnostic {
+    Complete,
+    /// `found` closes nothing that is open; `expected` is what would have closed the innermost
+    /// open delimiter, if there is one.  Columns count from 1.
+    Corrupt {
+        column: usize,
+        found: char,
+        expected: Option<char>,
+    },
+    Incomplete {
+        completion: String,
+    },
+    /// `found` is not a delimiter of any configured pair.
+    Invalid {
+        column: usize,
+        found: char,
+    },
+}
 
-    fn p1_score(&self) -> usize {
-        match self.kind {
-            Paren => 3,
-            Bracket => 57,
-            Brace => 1197,
-            Angle => 25137,
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Complete => write!(f, "complete"),
+            Self::Corrupt {
+                column,
+                found,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "column {}: expected '{}', found '{}'",
+                column, expected, found
+            ),
+            Self::Corrupt {
+                column,
+                found,
+                expected: None,
+            } => write!(f, "column {}: found '{}' with nothing open", column, found),
+            Self::Incomplete { completion } => write!(f, "incomplete: missing '{}'", completion),
+            Self::Invalid { column, found } => {
+                write!(f, "column {}: '{}' is not a delimiter", column, found)
+            }
         }
     }
+}
 
-    fn p2_score(&self) -> usize {
-        match self.kind {
-            Paren => 1,
-            Bracket => 2,
-            Brace => 3,
-            Angle => 4,
-        }
+impl Config {
+    fn pair_opened_by(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.open == c)
     }
-}
 
-enum Result {
-    Error(usize),
-    Incomplete(Vec<Delim>),
-}
+    fn pair_closed_by(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.close == c)
+    }
 
-/// Returns Some(score) if syntax error present
-/// Returns None if syntax error not present
-fn syntax_error_score(s: &str) -> Result {
-    let delims = s.chars().map(Delim::from).collect::<Vec<Delim>>();
-
-    let mut stack = vec![];
-    for delim in delims {
-        if delim.is_match(stack.last()) {
-            match delim.side {
-                Left => {
-                    stack.push(delim);
-                }
-                Right => {
-                    stack.pop();
+    fn check(&self, line: &str) -> Diagnostic {
+        let mut stack: Vec<&Pair> = vec![];
+        for (i, c) in line.chars().enumerate() {
+            if let Some(pair) = self.pair_opened_by(c) {
+                stack.push(pair);
+            } else if self.pair_closed_by(c).is_some() {
+                match stack.pop() {
+                    Some(open) if open.close == c => {}
+                    open => {
+                        return Diagnostic::Corrupt {
+                            column: i + 1,
+                            found: c,
+                            expected: open.map(|pair| pair.close),
+                        }
+                    }
                 }
+            } else {
+                return Diagnostic::Invalid {
+                    column: i + 1,
+                    found: c,
+                };
             }
+        }
+
+        if stack.is_empty() {
+            Diagnostic::Complete
         } else {
-            return Result::Error(delim.p1_score());
+            let completion = stack.iter().rev().map(|pair| pair.close).collect();
+            Diagnostic::Incomplete { completion }
         }
     }
 
-    Result::Incomplete(stack)
+    fn error_score(&self, found: char) -> usize {
+        self.pair_closed_by(found)
+            .map_or(0, |pair| pair.error_score)
+    }
+
+    fn completion_score(&self, completion: &str) -> usize {
+        completion
+            .chars()
+            .filter_map(|c| self.pair_closed_by(c))
+            .fold(0, |total_score, pair| {
+                total_score * self.completion_base + pair.completion_score
+            })
+    }
+
+    /// Appends the completion to every incomplete line.  Other lines are left as they are.
+    fn fix(&self, input: &str) -> String {
+        input
+            .lines()
+            .map(|line| match self.check(line) {
+                Diagnostic::Incomplete { completion } => format!("{}{}\n", line, completion),
+                _ => format!("{}\n", line),
+            })
+            .collect()
+    }
+}
+
+/// Runs the `fix` command, which prints the puzzle input with every incomplete line completed.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    match args {
+        ["fix"] => Ok(Config::default().fix(&input(DAY))),
+        _ => Err("usage: aoc2021 10 fix".to_string()),
+    }
 }
 
 fn p1(input: &str) -> usize {
+    let config = Config::default();
     input
         .lines()
-        .filter_map(|line| match syntax_error_score(line) {
-            Result::Error(x) => Some(x),
-            Result::Incomplete(_) => None,
+        .filter_map(|line| match config.check(line) {
+            Diagnostic::Corrupt { found, .. } => Some(config.error_score(found)),
+            _ => None,
         })
         .sum()
 }
 
-fn completion_score(stack: &[Delim]) -> usize {
-    stack
-        .iter()
-        .rev()
-        .map(Delim::p2_score)
-        .fold(0, |total_score, delim_score| total_score * 5 + delim_score)
-}
-
 fn p2(input: &str) -> usize {
+    let config = Config::default();
     input
         .lines()
-        .filter_map(|line| match syntax_error_score(line) {
-            Result::Incomplete(x) => Some(x),
-            Result::Error(_) => None,
+        .filter_map(|line| match config.check(line) {
+            Diagnostic::Incomplete { completion } => Some(config.completion_score(&completion)),
+            _ => None,
         })
-        .map(|x| completion_score(&x))
         .collect::<Vec<usize>>()
         .tap_mut(|v| v.sort())
         .pipe(|v| v[v.len() / 2])
@@ -201,4 +231,112 @@ mod tests {
         let input = input(DAY);
         assert_eq!(super::p2(&input), 2165057169);
     }
+
+    #[test]
+    fn check() {
+        let config = Config::default();
+        let lines = INPUT.lines().collect::<Vec<&str>>();
+
+        assert_eq!(
+            config.check(lines[2]),
+            Diagnostic::Corrupt {
+                column: 13,
+                found: '}',
+                expected: Some(']'),
+            }
+        );
+        assert_eq!(
+            config.check(lines[2]).to_string(),
+            "column 13: expected ']', found '}'"
+        );
+        assert_eq!(
+            config.check(lines[0]),
+            Diagnostic::Incomplete {
+                completion: "}}]])})]".to_string()
+            }
+        );
+        assert_eq!(config.completion_score("}}]])})]"), 288957);
+        assert_eq!(config.check("[<>]"), Diagnostic::Complete);
+        assert_eq!(
+            config.check("()]").to_string(),
+            "column 3: found ']' with nothing open"
+        );
+        assert_eq!(
+            config.check("(a)").to_string(),
+            "column 2: 'a' is not a delimiter"
+        );
+    }
+
+    #[test]
+    fn custom_config() {
+        let config = Config {
+            pairs: vec![
+                Pair {
+                    open: '/',
+                    close: '\\',
+                    error_score: 7,
+                    completion_score: 1,
+                },
+                Pair {
+                    open: '(',
+                    close: ')',
+                    error_score: 11,
+                    completion_score: 2,
+                },
+            ],
+            completion_base: 3,
+        };
+
+        assert_eq!(
+            config.check("/(\\"),
+            Diagnostic::Corrupt {
+                column: 3,
+                found: '\\',
+                expected: Some(')'),
+            }
+        );
+        assert_eq!(config.error_score('\\'), 7);
+        assert_eq!(
+            config.check("/(/"),
+            Diagnostic::Incomplete {
+                completion: "\\)\\".to_string()
+            }
+        );
+        assert_eq!(config.completion_score("\\)\\"), (3 + 2) * 3 + 1);
+        assert_eq!(
+            config.check("[]"),
+            Diagnostic::Invalid {
+                column: 1,
+                found: '['
+            }
+        );
+    }
+
+    #[test]
+    fn fix() {
+        let config = Config::default();
+        let fixed = config.fix(INPUT);
+
+        for (before, after) in INPUT.lines().zip(fixed.lines()) {
+            match config.check(before) {
+                Diagnostic::Incomplete { completion } => {
+                    assert_eq!(after, format!("{}{}", before, completion));
+                    assert_eq!(config.check(after), Diagnostic::Complete);
+                }
+                _ => assert_eq!(after, before),
+            }
+        }
+    }
+
+    #[test]
+    fn tool() {
+        let config = Config::default();
+        let fixed = super::tool(&["fix"]).unwrap();
+
+        assert_eq!(fixed.lines().count(), input(DAY).lines().count());
+        assert!(fixed
+            .lines()
+            .all(|line| !matches!(config.check(line), Diagnostic::Incomplete { .. })));
+        assert!(super::tool(&["fix", "now"]).is_err());
+    }
 }
@@ -1,20 +1,26 @@
 use crate::input;
+use crate::output::Solution;
 
 use std::fmt;
+use std::path::Path;
+
+mod d11_cycle;
+mod d11_frames;
+
+use d11_cycle::{Cycle, History};
+use d11_frames::{FrameFormat, Recorder};
 
 const DAY: usize = 11;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 struct Grid {
     xs: usize,
     ys: usize,
@@ -54,7 +60,12 @@ impl From<&str> for Grid {
 impl fmt::Debug for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f)?;
+        write!(f, "{}", self)
+    }
+}
 
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for y in 0..self.ys {
             for x in 0..self.xs {
                 let value = self.values[x][y];
@@ -126,9 +137,9 @@ impl Grid {
                     let xn = (x as i64) + xd;
                     let yn = (y as i64) + yd;
                     let valid = xn >= 0
-                        && xn <= (self.xs as i64) - 1
+                        && xn < (self.xs as i64)
                         && yn >= 0
-                        && yn <= (self.ys as i64) - 1
+                        && yn < (self.ys as i64)
                         && !(xd == &0 && yd == &0);
                     if valid {
                         Some((xn as usize, yn as usize))
@@ -151,6 +162,56 @@ impl Grid {
     }
 }
 
+/// Runs one of the commands for watching the puzzle's grid:
+/// - `cycle` finds when the grid first repeats and how often it repeats from then on;
+/// - `at STEP` prints the grid after any number of steps, however large;
+/// - `frames STEPS DIR [SCALE]` saves the grid after each step into `DIR`, as one text file or,
+///   given a `SCALE`, as PPM images with each octopus `SCALE` pixels across.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let number = |arg: &str| {
+        arg.parse::<usize>()
+            .map_err(|_| format!("'{}' is not a number", arg))
+    };
+    let mut grid = Grid::from(input(DAY).as_str());
+
+    match args {
+        ["cycle"] => {
+            let Cycle { offset, period } = History::from(grid).cycle();
+            Ok(format!(
+                "repeats every {} steps from step {}\n",
+                period, offset
+            ))
+        }
+        ["at", step] => {
+            let step = number(step)?;
+            let grid = History::from(grid).at(step);
+            Ok(format!(
+                "After step {} ({} flashes):\n{}",
+                step, grid.flashes, grid
+            ))
+        }
+        ["frames", steps, dir, scale @ ..] if scale.len() <= 1 => {
+            let format = match scale.first() {
+                Some(scale) => FrameFormat::Ppm {
+                    scale: number(scale)?.max(1),
+                },
+                None => FrameFormat::Text,
+            };
+            let mut recorder = Recorder::new(format);
+            recorder.record_steps(&mut grid, number(steps)?);
+            let paths = recorder
+                .save(Path::new(dir))
+                .map_err(|e| format!("could not save the frames: {}", e))?;
+
+            Ok(paths
+                .iter()
+                .map(|path| format!("{}\n", path.display()))
+                .collect())
+        }
+        _ => Err("usage: aoc2021 11 cycle | at STEP | frames STEPS DIR [SCALE]".to_string()),
+    }
+}
+
 fn p1(input: &str) -> usize {
     Grid::from(input).step_for(100)
 }
@@ -165,7 +226,7 @@ mod tests {
 
     use indoc::indoc;
 
-    const INPUT: &str = indoc! {"
+    pub(super) const INPUT: &str = indoc! {"
         5483143223
         2745854711
         5264556173
@@ -193,4 +254,25 @@ mod tests {
         let input = input(DAY);
         assert_eq!(super::p2(&input), 505);
     }
+
+    #[test]
+    fn tool() {
+        let cycle = super::tool(&["cycle"]).unwrap();
+        assert!(cycle.ends_with(" steps from step 505\n"));
+
+        let at = super::tool(&["at", "100"]).unwrap();
+        assert!(at.starts_with("After step 100 (1747 flashes):\n"));
+        assert_eq!(at.lines().count(), 11);
+        let synced = super::tool(&["at", "1000000505"]).unwrap();
+        assert!(synced.ends_with(&"0000000000\n".repeat(10)));
+
+        let dir = std::env::temp_dir().join(format!("aoc2021-d11-tool-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        let saved = super::tool(&["frames", "2", dir, "3"]).unwrap();
+        assert_eq!(saved.lines().count(), 3);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert!(super::tool(&["at", "soon"]).is_err());
+        assert!(super::tool(&["frames", "2"]).is_err());
+    }
 }
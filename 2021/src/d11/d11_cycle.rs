@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// The grid first returns to an earlier state after `offset + period` steps, and from step
+/// `offset` onwards repeats every `period` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub offset: usize,
+    pub period: usize,
+}
+
+/// Every state of a grid from the start until it first repeats.
+pub struct History {
+    grids: Vec<Grid>,
+    cycle: Cycle,
+}
+
+impl From<Grid> for History {
+    fn from(grid: Grid) -> Self {
+        let mut seen: HashMap<[[u8; 10]; 10], usize> = HashMap::new();
+        let mut grids = vec![];
+        let mut grid = grid;
+
+        let offset = loop {
+            if let Some(&offset) = seen.get(&grid.values) {
+                break offset;
+            }
+            seen.insert(grid.values, grids.len());
+            grids.push(grid);
+            grid.step();
+        };
+        let period = grids.len() - offset;
+        grids.push(grid);
+
+        Self {
+            grids,
+            cycle: Cycle { offset, period },
+        }
+    }
+}
+
+impl History {
+    pub fn cycle(&self) -> Cycle {
+        self.cycle
+    }
+
+    /// The grid after `step` steps, including the number of flashes so far.
+    pub fn at(&self, step: usize) -> Grid {
+        let Cycle { offset, period } = self.cycle;
+        if step < self.grids.len() {
+            return self.grids[step];
+        }
+
+        let laps = (step - offset) / period;
+        let mut grid = self.grids[offset + (step - offset) % period];
+        let flashes_per_lap = self.grids[offset + period].flashes - self.grids[offset].flashes;
+        grid.flashes += laps * flashes_per_lap;
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::d11::tests::INPUT;
+
+    #[test]
+    fn cycle() {
+        let history = History::from(Grid::from(INPUT));
+        assert_eq!(
+            history.cycle(),
+            Cycle {
+                offset: 195,
+                period: 10
+            }
+        );
+    }
+
+    #[test]
+    fn jump_matches_stepping() {
+        let initial = Grid::from(INPUT);
+        let history = History::from(initial);
+
+        let mut grid = initial;
+        for step in 0..400 {
+            assert_eq!(history.at(step), grid, "step {}", step);
+            grid.step();
+        }
+
+        assert_eq!(history.at(100).flashes, 1656);
+        let far = history.at(195 + 10 * 100_000_000);
+        assert_eq!(far.values, history.at(195).values);
+        assert_eq!(far.flashes, history.at(195).flashes + 100 * 100_000_000);
+    }
+}
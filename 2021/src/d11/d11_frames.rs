@@ -0,0 +1,130 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// All frames in one text file, each headed by its step number.
+    Text,
+    /// One PPM image per frame, each cell drawn as a `scale` by `scale` square.
+    Ppm { scale: usize },
+}
+
+/// Collects a rendering of the grid after each step.
+pub struct Recorder {
+    format: FrameFormat,
+    frames: Vec<String>,
+}
+
+impl Recorder {
+    pub fn new(format: FrameFormat) -> Self {
+        Self {
+            format,
+            frames: vec![],
+        }
+    }
+
+    pub fn record(&mut self, grid: &Grid) {
+        let step = self.frames.len();
+        let frame = match self.format {
+            FrameFormat::Text => format!("After step {}:\n{}\n", step, grid),
+            FrameFormat::Ppm { scale } => ppm(grid, scale),
+        };
+        self.frames.push(frame);
+    }
+
+    /// Records the grid as it is and after each of the next `steps` steps.
+    pub fn record_steps(&mut self, grid: &mut Grid, steps: usize) {
+        self.record(grid);
+        for _ in 0..steps {
+            grid.step();
+            self.record(grid);
+        }
+    }
+
+    /// Writes the frames into `dir`, returning the paths of the files written.
+    pub fn save(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        match self.format {
+            FrameFormat::Text => {
+                let path = dir.join("frames.txt");
+                fs::write(&path, self.frames.concat())?;
+                Ok(vec![path])
+            }
+            FrameFormat::Ppm { .. } => self
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(step, frame)| {
+                    let path = dir.join(format!("frame-{:04}.ppm", step));
+                    fs::write(&path, frame)?;
+                    Ok(path)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Flashing octopuses are white and the rest shade from black to blue as their energy rises.
+fn ppm(grid: &Grid, scale: usize) -> String {
+    let mut out = format!("P3\n{} {}\n255\n", grid.xs * scale, grid.ys * scale);
+    for y in 0..grid.ys * scale {
+        let row = (0..grid.xs * scale)
+            .map(|x| match grid.values[x / scale][y / scale] {
+                0 => "255 255 255".to_string(),
+                value => {
+                    let level = value as usize * 255 / 10;
+                    format!("0 {} {}", level / 2, level)
+                }
+            })
+            .collect::<Vec<String>>();
+        out += &row.join(" ");
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        let mut grid = Grid::from("11111\n19991\n19191\n19991\n11111\n");
+        let mut recorder = Recorder::new(FrameFormat::Text);
+        recorder.record_steps(&mut grid, 2);
+
+        assert_eq!(
+            recorder.frames.concat(),
+            "After step 0:\n11111\n19991\n19191\n19991\n11111\n\n\
+             After step 1:\n34543\n40004\n50005\n40004\n34543\n\n\
+             After step 2:\n45654\n51115\n61116\n51115\n45654\n\n"
+        );
+    }
+
+    #[test]
+    fn ppm() {
+        let mut recorder = Recorder::new(FrameFormat::Ppm { scale: 2 });
+        recorder.record(&Grid::from("05\n"));
+
+        assert_eq!(
+            recorder.frames[0],
+            "P3\n4 2\n255\n\
+             255 255 255 255 255 255 0 63 127 0 63 127\n\
+             255 255 255 255 255 255 0 63 127 0 63 127\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("aoc2021-d11-{}", std::process::id()));
+        recorder.record(&Grid::from("11\n"));
+        let paths = recorder.save(&dir).unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.join("frame-0000.ppm"), dir.join("frame-0001.ppm")]
+        );
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), recorder.frames[0]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
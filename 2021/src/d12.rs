@@ -1,115 +1,228 @@
 use crate::input;
-
-use tap::prelude::*;
+use crate::output::Solution;
 
 use std::collections::HashMap;
 use std::fmt;
 
 const DAY: usize = 12;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::try_time(DAY, 1, || p1(&input)),
+        Solution::try_time(DAY, 2, || p2(&input)),
+    ]
 }
 
+/// A cave, named by a slice of the input it was parsed from.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-enum Node {
+enum Node<'a> {
     Start,
     End,
-    Big(char),
-    Small(char),
+    Big(&'a str),
+    Small(&'a str),
 }
 
-impl Node {
+impl<'a> Node<'a> {
     fn is_small(&self) -> bool {
         matches!(self, Node::Small(_))
     }
-}
 
-impl fmt::Debug for Node {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Node::Start => write!(f, "start"),
-            Node::End => write!(f, "end"),
-            Node::Big(c) => write!(f, "{}", c),
-            Node::Small(c) => write!(f, "{}", c),
-        }
-    }
-}
-
-impl From<&str> for Node {
-    fn from(s: &str) -> Self {
+    /// Returns `None` for an empty name.
+    fn parse(s: &'a str) -> Option<Self> {
         match s {
-            "start" => Node::Start,
-            "end" => Node::End,
+            "start" => Some(Node::Start),
+            "end" => Some(Node::End),
             _ => {
-                let mut chars = s.chars();
-                let c = chars.next().unwrap();
+                let c = s.chars().next()?;
                 if c.is_lowercase() {
-                    Node::Small(c)
+                    Some(Node::Small(s))
                 } else {
-                    Node::Big(c)
+                    Some(Node::Big(s))
                 }
             }
         }
     }
 }
 
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Start => write!(f, "start"),
+            Node::End => write!(f, "end"),
+            Node::Big(name) => write!(f, "{}", name),
+            Node::Small(name) => write!(f, "{}", name),
+        }
+    }
+}
+
 #[derive(Debug)]
-struct Graph {
-    nodes: HashMap<Node, Vec<Node>>,
+struct Graph<'a> {
+    nodes: HashMap<Node<'a>, Vec<Node<'a>>>,
+    /// Gives each small cave its bit in a visited set.
+    small: HashMap<Node<'a>, usize>,
 }
 
-impl Graph {
+impl Graph<'_> {
     fn new() -> Self {
         Self {
             nodes: HashMap::new(),
+            small: HashMap::new(),
         }
     }
 }
 
-impl From<&str> for Graph {
-    fn from(s: &str) -> Self {
-        s.lines()
-            .map(|line| {
-                line.split_once("-")
-                    .unwrap()
-                    .pipe(|(a, b)| (Node::from(a), Node::from(b)))
-            })
-            .fold(Graph::new(), |mut graph, (a, b)| {
-                if !matches!(b, Node::Start) && !matches!(a, Node::End) {
-                    graph.nodes.entry(a).or_insert(Vec::new()).push(b);
-                }
-                if !matches!(a, Node::Start) && !matches!(b, Node::End) {
-                    graph.nodes.entry(b).or_insert(Vec::new()).push(a);
-                }
-                graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GraphError {
+    Malformed(String),
+    /// Two adjacent big caves allow paths that bounce between them forever.
+    BigToBig(String, String),
+    TooManySmallCaves,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed(line) => write!(f, "expected 'a-b' but got '{}'", line),
+            Self::BigToBig(a, b) => write!(
+                f,
+                "big caves {} and {} are connected, so there are infinitely many paths",
+                a, b
+            ),
+            Self::TooManySmallCaves => write!(f, "more than 64 small caves"),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Graph<'a> {
+    type Error = GraphError;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let mut graph = Graph::new();
+        for line in s.lines() {
+            let (a, b) = line
+                .split_once('-')
+                .and_then(|(a, b)| Node::parse(a).zip(Node::parse(b)))
+                .ok_or_else(|| GraphError::Malformed(line.to_string()))?;
+
+            if let (Node::Big(a), Node::Big(b)) = (a, b) {
+                return Err(GraphError::BigToBig(a.to_string(), b.to_string()));
+            }
+
+            if !matches!(b, Node::Start) && !matches!(a, Node::End) {
+                graph.nodes.entry(a).or_default().push(b);
+            }
+            if !matches!(a, Node::Start) && !matches!(b, Node::End) {
+                graph.nodes.entry(b).or_default().push(a);
+            }
+            for node in [a, b].into_iter().filter(Node::is_small) {
+                let next = graph.small.len();
+                graph.small.entry(node).or_insert(next);
+            }
+        }
+
+        if graph.small.len() > 64 {
+            return Err(GraphError::TooManySmallCaves);
+        }
+
+        Ok(graph)
+    }
+}
+
+impl<'a> Graph<'a> {
+    /// Counts the paths from start to end that visit small caves at most once, except for one
+    /// small cave that may be visited twice if `revisit` is set.
+    fn count_paths(&self, revisit: bool) -> usize {
+        let mut memo = HashMap::new();
+        self.count_from(Node::Start, 0, !revisit, &mut memo)
+    }
+
+    /// Paths from `node` onwards depend only on which small caves have been visited and whether
+    /// the revisit has been spent, so they are counted once per such state.
+    fn count_from(
+        &self,
+        node: Node<'a>,
+        visited: u64,
+        revisited: bool,
+        memo: &mut HashMap<(Node<'a>, u64, bool), usize>,
+    ) -> usize {
+        if matches!(node, Node::End) {
+            return 1;
+        }
+        if let Some(&count) = memo.get(&(node, visited, revisited)) {
+            return count;
+        }
+
+        let count = self
+            .nodes
+            .get(&node)
+            .map(|neighbors| {
+                neighbors
+                    .iter()
+                    .map(|&neighbor| match self.small.get(&neighbor) {
+                        None => self.count_from(neighbor, visited, revisited, memo),
+                        Some(&bit) if visited & 1 << bit == 0 => {
+                            self.count_from(neighbor, visited | 1 << bit, revisited, memo)
+                        }
+                        Some(_) if !revisited => self.count_from(neighbor, visited, true, memo),
+                        Some(_) => 0,
+                    })
+                    .sum()
             })
+            .unwrap_or(0);
+
+        memo.insert((node, visited, revisited), count);
+        count
+    }
+
+    /// Lists every path counted by `count_paths`.  Only practical for small graphs.
+    fn paths(&self, revisit: bool) -> Paths<'_, 'a> {
+        let mut start = Path::new();
+        start.push(Node::Start);
+
+        Paths {
+            graph: self,
+            revisit,
+            stack: vec![start],
+        }
     }
 }
 
-fn allow_one_small(_path: &Path) -> usize {
-    1
+/// Runs the `paths [--revisit]` command, which lists every path through the puzzle's caves,
+/// letting one small cave be visited twice with `--revisit`.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let revisit = match args {
+        ["paths"] => false,
+        ["paths", "--revisit"] => true,
+        _ => return Err("usage: aoc2021 12 paths [--revisit]".to_string()),
+    };
+    let input = input(DAY);
+    let graph = Graph::try_from(input.as_str()).map_err(|e| e.to_string())?;
+
+    Ok(graph
+        .paths(revisit)
+        .map(|path| format!("{}\n", path))
+        .collect())
 }
 
-fn p1(input: &str) -> usize {
-    let graph = Graph::from(input);
+fn p1(input: &str) -> Result<usize, GraphError> {
+    Ok(Graph::try_from(input)?.count_paths(false))
+}
 
-    let mut path = Path::new();
-    visit_node(&graph, &mut path, Node::Start, allow_one_small)
+#[derive(Clone)]
+struct Path<'a> {
+    nodes: Vec<Node<'a>>,
+    counts: HashMap<Node<'a>, usize>,
 }
 
-struct Path {
-    nodes: Vec<Node>,
-    counts: HashMap<Node, usize>,
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nodes = self.nodes.iter().map(|node| format!("{:?}", node));
+        write!(f, "{}", nodes.collect::<Vec<String>>().join(","))
+    }
 }
 
-impl Path {
+impl<'a> Path<'a> {
     fn new() -> Self {
         Self {
             nodes: Vec::new(),
@@ -117,74 +230,58 @@ impl Path {
         }
     }
 
-    fn push(&mut self, node: Node) {
+    fn push(&mut self, node: Node<'a>) {
         self.nodes.push(node);
         if node.is_small() {
             *self.counts.entry(node).or_insert(0) += 1;
         }
     }
 
-    fn pop(&mut self) {
-        let node = self.nodes.pop().unwrap();
-        if node.is_small() {
-            *self.counts.entry(node).or_insert(1) -= 1;
-        }
-    }
-
     fn contains_two_of_same_small(&self) -> bool {
         self.counts.iter().any(|(_node, count)| *count == 2)
     }
 
-    fn allow_two_of_same_small(&self) -> usize {
-        if self.contains_two_of_same_small() {
-            1
-        } else {
-            2
+    fn can_visit(&self, node: Node<'a>, revisit: bool) -> bool {
+        match self.counts.get(&node).copied().unwrap_or(0) {
+            0 => true,
+            1 => revisit && !self.contains_two_of_same_small(),
+            _ => false,
         }
     }
 }
 
-fn visit_node<F>(graph: &Graph, path: &mut Path, node: Node, allowed: F) -> usize
-where
-    F: Fn(&Path) -> usize + Copy,
-{
-    if matches!(node, Node::End) {
-        return 1;
-    }
+/// Depth-first iterator over the complete paths of a graph.
+struct Paths<'g, 'a> {
+    graph: &'g Graph<'a>,
+    revisit: bool,
+    stack: Vec<Path<'a>>,
+}
 
-    if node.is_small() {
-        let occurrences = path.counts.get(&node).unwrap_or(&0);
-        if occurrences > &0 && occurrences >= &allowed(path) {
-            return 0;
-        }
-    }
+impl<'a> Iterator for Paths<'_, 'a> {
+    type Item = Path<'a>;
 
-    graph
-        .nodes
-        .get(&node)
-        .and_then(|neighbors| {
-            path.push(node);
-            let valid_paths = neighbors
-                .iter()
-                .map(|neighbor| visit_node(graph, path, *neighbor, allowed))
-                .sum();
-            path.pop();
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(path) = self.stack.pop() {
+            let node = *path.nodes.last().unwrap();
+            if matches!(node, Node::End) {
+                return Some(path);
+            }
 
-            Some(valid_paths)
-        })
-        .unwrap_or(0)
-}
+            for &neighbor in self.graph.nodes.get(&node).into_iter().flatten().rev() {
+                if path.can_visit(neighbor, self.revisit) {
+                    let mut next = path.clone();
+                    next.push(neighbor);
+                    self.stack.push(next);
+                }
+            }
+        }
 
-fn p2(input: &str) -> usize {
-    let graph = Graph::from(input);
+        None
+    }
+}
 
-    let mut path = Path::new();
-    visit_node(
-        &graph,
-        &mut path,
-        Node::Start,
-        Path::allow_two_of_same_small,
-    )
+fn p2(input: &str) -> Result<usize, GraphError> {
+    Ok(Graph::try_from(input)?.count_paths(true))
 }
 
 #[cfg(test)]
@@ -218,19 +315,86 @@ mod tests {
 
     #[test]
     fn p1() {
-        assert_eq!(super::p1(INPUT1), 10);
-        assert_eq!(super::p1(INPUT2), 19);
+        assert_eq!(super::p1(INPUT1), Ok(10));
+        assert_eq!(super::p1(INPUT2), Ok(19));
 
         let input = input(DAY);
-        assert_eq!(super::p1(&input), 3887);
+        assert_eq!(super::p1(&input), Ok(3887));
     }
 
     #[test]
     fn p2() {
-        assert_eq!(super::p2(INPUT1), 36);
-        assert_eq!(super::p2(INPUT2), 103);
+        assert_eq!(super::p2(INPUT1), Ok(36));
+        assert_eq!(super::p2(INPUT2), Ok(103));
 
         let input = input(DAY);
-        assert_eq!(super::p2(&input), 104834);
+        assert_eq!(super::p2(&input), Ok(104834));
+    }
+
+    #[test]
+    fn paths() {
+        let graph = Graph::try_from(INPUT1).unwrap();
+        let paths = graph
+            .paths(false)
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 10);
+        assert!(paths.contains(&"start,A,b,A,c,A,end".to_string()));
+        assert!(paths.contains(&"start,b,end".to_string()));
+
+        for input in [INPUT1, INPUT2] {
+            let graph = Graph::try_from(input).unwrap();
+            for revisit in [false, true] {
+                assert_eq!(graph.paths(revisit).count(), graph.count_paths(revisit));
+            }
+        }
+    }
+
+    #[test]
+    fn tool() {
+        assert_eq!(super::tool(&["paths"]).unwrap().lines().count(), 3887);
+        let revisits = super::tool(&["paths", "--revisit"]).unwrap();
+        assert_eq!(revisits.lines().count(), 104834);
+        assert!(revisits.lines().all(|path| path.starts_with("start,")));
+        assert!(super::tool(&["paths", "--twice"]).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Graph::try_from("start-A\nA-B\nB-end\n").unwrap_err(),
+            GraphError::BigToBig("A".to_string(), "B".to_string())
+        );
+        assert_eq!(
+            Graph::try_from("start-A\nA-B\n").unwrap_err().to_string(),
+            "big caves A and B are connected, so there are infinitely many paths"
+        );
+        assert_eq!(
+            Graph::try_from("start A").unwrap_err(),
+            GraphError::Malformed("start A".to_string())
+        );
+        assert_eq!(
+            super::p2("start A"),
+            Err(GraphError::Malformed("start A".to_string()))
+        );
+        for line in ["a-", "-b", "-"] {
+            assert_eq!(
+                Graph::try_from(line).unwrap_err(),
+                GraphError::Malformed(line.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn full_names() {
+        // Caves that share a first letter are still distinct.
+        let graph = Graph::try_from("start-dc\nstart-dx\ndc-end\ndx-end\n").unwrap();
+        assert_eq!(graph.small.len(), 2);
+        assert_eq!(graph.count_paths(false), 2);
+
+        let paths = graph.paths(false).map(|path| path.to_string());
+        let mut paths = paths.collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["start,dc,end", "start,dx,end"]);
     }
 }
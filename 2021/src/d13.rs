@@ -1,18 +1,16 @@
 use crate::input;
+use crate::output::Solution;
 
 use std::collections::HashSet;
 
 const DAY: usize = 13;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    let p2_output = p2(&input);
-    output += &format!("d{:02}p2: in {:?}\n{}\n", DAY, time.elapsed(), p2_output);
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -113,7 +111,13 @@ fn p1(input: &str) -> usize {
     fold_many(&input.points, *fold).len()
 }
 
+/// Reads the letters drawn by the folded dots, or shows the dots if they are not letters.
 fn p2(input: &str) -> String {
+    let s = render(input);
+    aoc_ocr::recognise_art(&s, '#').unwrap_or(s)
+}
+
+fn render(input: &str) -> String {
     let input = Input::from(input);
 
     let points = input
@@ -194,16 +198,6 @@ mod tests {
         );
 
         let input = input(DAY);
-        assert_eq!(
-            super::p2(&input),
-            indoc! {"
-            #### ###  #  # #### #    ###  ###  ### 
-            #    #  # #  # #    #    #  # #  # #  #
-            ###  #  # #  # ###  #    #  # ###  #  #
-            #    ###  #  # #    #    ###  #  # ### 
-            #    #    #  # #    #    #    #  # # # 
-            #### #     ##  #### #### #    ###  #  #
-        "}
-        );
+        assert_eq!(super::p2(&input), "EPUELPBR");
     }
 }
@@ -1,20 +1,21 @@
 use crate::input;
+use crate::matrix::Matrix;
+use crate::output::Solution;
 
 use itertools::Itertools;
 use itertools::MinMaxResult::MinMax;
+use num_bigint::BigUint;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 const DAY: usize = 14;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::time(DAY, 2, || p2(&input)),
+    ]
 }
 
 type Pair = (char, char);
@@ -61,49 +62,174 @@ impl From<&str> for Input {
     }
 }
 
-fn p1(input: &str) -> usize {
-    let input = Input::from(input);
-    polymerize(&input, 10)
+/// Counts elements after any number of insertion steps without building the polymer.
+///
+/// The polymer is tracked as counts of its adjacent pairs, each of which turns into two pairs,
+/// or stays as it is if it has no rule.
+struct Polymer {
+    elements: Vec<char>,
+    template: Vec<char>,
+    /// The element inserted into each pair, indexed by `first * elements.len() + second`.
+    inserts: Vec<Option<usize>>,
 }
 
-// Inspired by: https://github.com/MrRobb/advent-of-code-2021/blob/main/src/day14.rs
-fn polymerize(input: &Input, steps: usize) -> usize {
-    let template_pair_counts = input.template.chars().tuple_windows().counts();
-
-    let initial_char_counts = input.template.chars().counts();
-
-    let (_, char_counts) = (0..steps).fold(
-        (template_pair_counts, initial_char_counts),
-        |(pair_counts, mut char_counts), _| {
-            let next_pair_counts = pair_counts.iter().fold(
-                pair_counts.clone(),
-                |mut next_pair_counts, (pair, count)| {
-                    let insertion = input.rules.get(pair).unwrap();
-                    // We only need to calculate pair counts...
-                    *next_pair_counts.entry((pair.0, *insertion)).or_default() += count;
-                    *next_pair_counts.entry((*insertion, pair.1)).or_default() += count;
-                    *next_pair_counts.entry(*pair).or_default() -= count;
-                    // ...so that we can calculate char counts
-                    *char_counts.entry(*insertion).or_default() += count;
-
-                    next_pair_counts
-                },
-            );
+impl From<&Input> for Polymer {
+    fn from(input: &Input) -> Self {
+        let elements = input
+            .template
+            .chars()
+            .chain(input.rules.iter().flat_map(|(&(a, b), &c)| [a, b, c]))
+            .sorted()
+            .dedup()
+            .collect::<Vec<char>>();
 
-            (next_pair_counts, char_counts)
-        },
-    );
+        let index = |c: char| elements.binary_search(&c).unwrap();
+        let inserts = elements
+            .iter()
+            .copied()
+            .cartesian_product(elements.iter().copied())
+            .map(|pair| input.rules.get(&pair).map(|&c| index(c)))
+            .collect();
 
-    if let MinMax(min, max) = char_counts.into_values().minmax() {
-        (max - min) as usize
-    } else {
-        unreachable!()
+        Self {
+            elements,
+            template: input.template.chars().collect(),
+            inserts,
+        }
+    }
+}
+
+impl Polymer {
+    /// Counting pairs one step at a time is fastest for the step counts the puzzle asks for.
+    /// Beyond this, or once a count no longer fits in a `u64`, steps are taken by matrix
+    /// powers instead.
+    const MAX_DIRECT_STEPS: u64 = 64;
+
+    fn index(&self, c: char) -> usize {
+        self.elements.binary_search(&c).unwrap()
+    }
+
+    /// The pairs that a pair turns into in one step.
+    fn successors(&self, pair: usize) -> Vec<usize> {
+        let n = self.elements.len();
+        match self.inserts[pair] {
+            Some(c) => vec![pair / n * n + c, c * n + pair % n],
+            None => vec![pair],
+        }
+    }
+
+    /// Takes one step, or returns `None` if a count overflows.
+    fn step(&self, pairs: &[u64]) -> Option<Vec<u64>> {
+        let mut next = vec![0u64; pairs.len()];
+        for (pair, &count) in pairs.iter().enumerate() {
+            for successor in self.successors(pair) {
+                next[successor] = next[successor].checked_add(count)?;
+            }
+        }
+
+        Some(next)
+    }
+
+    /// Takes any number of steps at once by raising the one-step linear map on pair counts to
+    /// the `steps`th power.
+    fn jump(&self, pairs: &[u64], steps: u64) -> Vec<BigUint> {
+        let mut transition = Matrix::zero(pairs.len());
+        for pair in 0..pairs.len() {
+            for successor in self.successors(pair) {
+                transition.increment(successor, pair);
+            }
+        }
+
+        transition.pow(steps).apply(pairs)
+    }
+
+    /// The number of each element present after `steps` steps.
+    fn counts(&self, steps: u64) -> BTreeMap<char, BigUint> {
+        let n = self.elements.len();
+        let mut pairs = vec![0; n * n];
+        for (a, b) in self.template.iter().copied().tuple_windows() {
+            pairs[self.index(a) * n + self.index(b)] += 1;
+        }
+        let stepped = if steps <= Self::MAX_DIRECT_STEPS {
+            (0..steps).try_fold(pairs.clone(), |pairs, _| self.step(&pairs))
+        } else {
+            None
+        };
+        let pairs = match stepped {
+            Some(stepped) => stepped.into_iter().map(BigUint::from).collect(),
+            None => self.jump(&pairs, steps),
+        };
+
+        // Every element starts a pair except the last, which never changes.
+        let mut counts = BTreeMap::new();
+        for (i, count) in pairs.into_iter().enumerate() {
+            if count != BigUint::default() {
+                *counts.entry(self.elements[i / n]).or_default() += count;
+            }
+        }
+        if let Some(&last) = self.template.last() {
+            *counts.entry(last).or_default() += 1u8;
+        }
+
+        counts
+    }
+}
+
+/// Builds the polymer itself, which doubles in length every step.
+fn expand(input: &Input, steps: usize) -> String {
+    (0..steps).fold(input.template.clone(), |polymer, _| {
+        let mut next = String::with_capacity(polymer.len() * 2);
+        next.extend(polymer.chars().take(1));
+        for (a, b) in polymer.chars().tuple_windows() {
+            next.extend(input.rules.get(&(a, b)));
+            next.push(b);
+        }
+        next
+    })
+}
+
+/// The most steps `expand` is run for on request, by which the puzzle's polymer is already over
+/// half a million elements long.
+const MAX_EXPAND_STEPS: usize = 15;
+
+/// Runs the `expand STEPS` command, which prints the puzzle's polymer after up to
+/// `MAX_EXPAND_STEPS` steps.
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    let usage = || {
+        format!(
+            "usage: aoc2021 14 expand STEPS, with at most {} steps",
+            MAX_EXPAND_STEPS
+        )
+    };
+    let steps = match args {
+        ["expand", steps] => steps.parse::<usize>().map_err(|_| usage())?,
+        _ => return Err(usage()),
+    };
+    if steps > MAX_EXPAND_STEPS {
+        return Err(usage());
     }
+    let input = input(DAY);
+
+    Ok(expand(&Input::from(input.as_str()), steps) + "\n")
 }
 
-fn p2(input: &str) -> usize {
+fn spread(input: &str, steps: u64) -> BigUint {
     let input = Input::from(input);
-    polymerize(&input, 40)
+    let counts = Polymer::from(&input).counts(steps);
+
+    if let MinMax(min, max) = counts.into_values().minmax() {
+        max - min
+    } else {
+        BigUint::default()
+    }
+}
+
+fn p1(input: &str) -> BigUint {
+    spread(input, 10)
+}
+
+fn p2(input: &str) -> BigUint {
+    spread(input, 40)
 }
 
 #[cfg(test)]
@@ -135,17 +261,117 @@ mod tests {
 
     #[test]
     fn p1() {
-        assert_eq!(super::p1(INPUT), 1588);
+        assert_eq!(super::p1(INPUT), BigUint::from(1588u32));
 
         let input = input(DAY);
-        assert_eq!(super::p1(&input), 3143);
+        assert_eq!(super::p1(&input), BigUint::from(3143u32));
     }
 
     #[test]
     fn p2() {
-        assert_eq!(super::p2(INPUT), 2188189693529);
+        assert_eq!(super::p2(INPUT), BigUint::from(2188189693529u64));
 
         let input = input(DAY);
-        assert_eq!(super::p2(&input), 4110215602456);
+        assert_eq!(super::p2(&input), BigUint::from(4110215602456u64));
+    }
+
+    #[test]
+    fn counts() {
+        let polymer = Polymer::from(&Input::from(INPUT));
+        let counts = polymer.counts(10);
+        assert_eq!(counts[&'B'], BigUint::from(1749u32));
+        assert_eq!(counts[&'C'], BigUint::from(298u32));
+        assert_eq!(counts[&'H'], BigUint::from(161u32));
+        assert_eq!(counts[&'N'], BigUint::from(865u32));
+        assert_eq!(
+            polymer.counts(0).values().sum::<BigUint>(),
+            BigUint::from(4u32)
+        );
+    }
+
+    #[test]
+    fn many_steps() {
+        // Every pair in the example has a rule, so the polymer doubles its gaps each step and
+        // has 3 * 2^n + 1 elements after n steps.
+        let polymer = Polymer::from(&Input::from(INPUT));
+        for steps in [64, 65, 200] {
+            let length = polymer.counts(steps).into_values().sum::<BigUint>();
+            assert_eq!(
+                length,
+                (BigUint::from(3u8) << steps) + 1u8,
+                "{} steps",
+                steps
+            );
+        }
+    }
+
+    #[test]
+    fn jump_matches_steps() {
+        let polymer = Polymer::from(&Input::from(INPUT));
+        let pairs = (0..16).collect::<Vec<u64>>();
+        let stepped = (0..=30).scan(pairs.clone(), |pairs, _| {
+            let current = pairs.clone();
+            *pairs = polymer.step(pairs).unwrap();
+            Some(current)
+        });
+        for (steps, stepped) in stepped.enumerate() {
+            let stepped = stepped.into_iter().map(BigUint::from).collect::<Vec<_>>();
+            assert_eq!(
+                polymer.jump(&pairs, steps as u64),
+                stepped,
+                "{} steps",
+                steps
+            );
+        }
+
+        assert_eq!(polymer.step(&[u64::MAX; 16]), None);
+    }
+
+    #[test]
+    fn tool() {
+        let input = Input::from(input(DAY).as_str());
+        let expanded = super::tool(&["expand", "10"]).unwrap();
+        assert_eq!(expanded, expand(&input, 10) + "\n");
+        assert_eq!(
+            expanded.trim_end().len(),
+            (input.template.len() - 1) * 1024 + 1
+        );
+
+        assert!(super::tool(&["expand", "16"]).is_err());
+        assert!(super::tool(&["expand", "-1"]).is_err());
+    }
+
+    #[test]
+    fn matches_expansion() {
+        let partial = indoc! {"
+            ABCAD
+
+            AB -> C
+            CA -> A
+            BA -> D
+            DD -> B
+        "};
+
+        for input in [INPUT, partial] {
+            let input = Input::from(input);
+            let polymer = Polymer::from(&input);
+            for steps in 0..=8 {
+                let expanded = expand(&input, steps);
+                let expected = expanded
+                    .chars()
+                    .counts()
+                    .into_iter()
+                    .map(|(c, n)| (c, BigUint::from(n)));
+                assert_eq!(
+                    polymer.counts(steps as u64),
+                    expected.collect::<BTreeMap<char, BigUint>>(),
+                    "{} after {} steps",
+                    input.template,
+                    steps
+                );
+            }
+        }
+
+        assert_eq!(expand(&Input::from(INPUT), 2), "NBCCNBBBCBHCB");
     }
 }
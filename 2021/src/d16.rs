@@ -1,25 +1,28 @@
 use crate::input;
+use crate::output::Solution;
+
+use std::fmt;
 
 #[cfg(feature = "nom")]
 mod d16_nom;
-#[cfg(feature = "nom")]
-use d16_nom::*;
 
 #[cfg(feature = "bitvec")]
 mod d16_bitvec;
-#[cfg(feature = "bitvec")]
-use d16_bitvec::*;
+
+#[cfg(not(any(feature = "bitvec", feature = "nom")))]
+compile_error!("day 16 needs at least one of the `bitvec` or `nom` features");
+
+mod d16_encode;
+mod d16_expr;
 
 const DAY: usize = 16;
 
-pub fn run() -> String {
+pub fn run() -> Vec<Solution> {
     let input = input(DAY);
-    let mut output = String::new();
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p1: {} in {:?}\n", DAY, p1(&input), time.elapsed());
-    let time = std::time::Instant::now();
-    output += &format!("d{:02}p2: {} in {:?}\n", DAY, p2(&input), time.elapsed());
-    output
+    vec![
+        Solution::time(DAY, 1, || p1(&input)),
+        Solution::try_time(DAY, 2, || p2(&input)),
+    ]
 }
 
 #[derive(Debug, Clone)]
@@ -109,6 +112,20 @@ impl From<u8> for Op {
     }
 }
 
+impl From<Op> for u8 {
+    fn from(op: Op) -> Self {
+        match op {
+            Op::Sum => 0,
+            Op::Product => 1,
+            Op::Minimum => 2,
+            Op::Maximum => 3,
+            Op::GreaterThan => 5,
+            Op::LessThan => 6,
+            Op::EqualTo => 7,
+        }
+    }
+}
+
 impl Packet {
     fn version_sum(&self) -> usize {
         let version: usize = self.version.into();
@@ -120,76 +137,148 @@ impl Packet {
             }
     }
 
-    fn eval(&self) -> usize {
+    /// Evaluates the expression, failing on overflow or a wrong number of operands.
+    fn eval(&self) -> Result<u64, EvalError> {
         match &self.typ {
-            Type::Literal(v) => *v as usize,
+            Type::Literal(v) => Ok(*v),
             Type::Operator(op, children) => {
-                let children = children.iter().map(Packet::eval);
-                match op {
-                    Op::Sum => children.sum(),
-                    Op::Product => children.product(),
-                    Op::Minimum => children.min().unwrap(),
-                    Op::Maximum => children.max().unwrap(),
-                    Op::GreaterThan => children.greater_than() as usize,
-                    Op::LessThan => children.less_than() as usize,
-                    Op::EqualTo => children.equal_to() as usize,
+                let values = children
+                    .iter()
+                    .map(Packet::eval)
+                    .collect::<Result<Vec<u64>, EvalError>>()?;
+                let overflow = || EvalError::Overflow(*op);
+                let operand_count = |expected| EvalError::OperandCount {
+                    op: *op,
+                    expected,
+                    found: values.len(),
+                };
+
+                match (op, values.as_slice()) {
+                    (Op::Sum, _) => values
+                        .iter()
+                        .try_fold(0u64, |acc, &v| acc.checked_add(v))
+                        .ok_or_else(overflow),
+                    (Op::Product, _) => values
+                        .iter()
+                        .try_fold(1u64, |acc, &v| acc.checked_mul(v))
+                        .ok_or_else(overflow),
+                    (Op::Minimum, _) => values
+                        .iter()
+                        .copied()
+                        .min()
+                        .ok_or_else(|| operand_count("at least 1")),
+                    (Op::Maximum, _) => values
+                        .iter()
+                        .copied()
+                        .max()
+                        .ok_or_else(|| operand_count("at least 1")),
+                    (Op::GreaterThan, [a, b]) => Ok((a > b) as u64),
+                    (Op::LessThan, [a, b]) => Ok((a < b) as u64),
+                    (Op::EqualTo, [a, b]) => Ok((a == b) as u64),
+                    (Op::GreaterThan | Op::LessThan | Op::EqualTo, _) => Err(operand_count("2")),
                 }
             }
         }
     }
 }
 
-pub trait ComparisonIteratorAdapter
-where
-    Self: Sized + Iterator,
-{
-    fn greater_than(self) -> bool;
-    fn less_than(self) -> bool;
-    fn equal_to(self) -> bool;
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EvalError {
+    Overflow(Op),
+    OperandCount {
+        op: Op,
+        expected: &'static str,
+        found: usize,
+    },
 }
 
-impl<I> ComparisonIteratorAdapter for I
-where
-    I: Iterator,
-    I::Item: PartialOrd + PartialEq,
-{
-    fn greater_than(mut self) -> bool {
-        self.next().unwrap() > self.next().unwrap()
-    }
-
-    fn less_than(mut self) -> bool {
-        self.next().unwrap() < self.next().unwrap()
-    }
-
-    fn equal_to(mut self) -> bool {
-        self.next().unwrap() == self.next().unwrap()
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Overflow(op) => write!(f, "{} overflowed", op.name()),
+            Self::OperandCount {
+                op,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "{} expects {} operands but has {}",
+                    op.name(),
+                    expected,
+                    found
+                )
+            }
+        }
     }
 }
 
-fn p1(s: &str) -> usize {
-    let input = Input::from(s);
+/// Turns the bytes of a transmission into its outermost packet.
+trait Decoder {
+    fn name(&self) -> &'static str;
+    fn decode(&self, input: &Input) -> Packet;
+}
 
+/// Every enabled decoder.  The first one is used to solve the puzzle.
+const BACKENDS: &[&dyn Decoder] = &[
     #[cfg(feature = "bitvec")]
-    {
-        let mut bits = Bits::from(&input);
-        Packet::from(&mut bits).version_sum()
+    &d16_bitvec::BitvecDecoder,
+    #[cfg(feature = "nom")]
+    &d16_nom::NomDecoder,
+];
+
+/// Runs one of the transmission commands:
+///
+/// * `encode <expr>`: encodes a call expression such as `sum(1, max(2, 3))` as a transmission
+/// * `infix`: renders the puzzle's transmission as an infix expression
+/// * `bench [<rounds>]`: times each backend decoding the puzzle's transmission, 1000 times by
+///   default
+pub fn tool(args: &[&str]) -> Result<String, String> {
+    match args {
+        ["bench"] => Ok(bench(1000)),
+        ["bench", rounds] => match rounds.parse() {
+            Ok(rounds) if rounds > 0 => Ok(bench(rounds)),
+            _ => Err(format!("invalid number of rounds '{}'", rounds)),
+        },
+        ["infix"] => Ok(decode(&input(DAY)).to_infix() + "\n"),
+        ["encode", expr] => {
+            let packet = expr.parse::<Packet>().map_err(|error| error.to_string())?;
+            let transmission = packet.encode(None).map_err(|error| error.to_string())?;
+            Ok(transmission + "\n")
+        }
+        _ => Err("usage: aoc2021 16 encode EXPR | infix | bench [ROUNDS]".to_string()),
     }
+}
 
-    #[cfg(feature = "nom")]
-    Packet::from(input.bytes.as_ref()).version_sum()
+/// A rough mean time per decode for each backend.
+fn bench(rounds: u32) -> String {
+    let input = Input::from(input(DAY).as_str());
+    BACKENDS
+        .iter()
+        .map(|backend| {
+            let start = std::time::Instant::now();
+            for _ in 0..rounds {
+                std::hint::black_box(backend.decode(std::hint::black_box(&input)));
+            }
+            format!(
+                "{:>6}: {:?} per decode\n",
+                backend.name(),
+                start.elapsed() / rounds
+            )
+        })
+        .collect()
 }
 
-fn p2(s: &str) -> usize {
-    let input = Input::from(s);
+fn decode(s: &str) -> Packet {
+    BACKENDS[0].decode(&Input::from(s))
+}
 
-    #[cfg(feature = "bitvec")]
-    {
-        let mut bits = Bits::from(&input);
-        Packet::from(&mut bits).eval()
-    }
+fn p1(s: &str) -> usize {
+    decode(s).version_sum()
+}
 
-    #[cfg(feature = "nom")]
-    Packet::from(input.bytes.as_ref()).eval()
+fn p2(s: &str) -> Result<u64, EvalError> {
+    decode(s).eval()
 }
 
 #[cfg(test)]
@@ -209,16 +298,118 @@ mod tests {
 
     #[test]
     fn p2() {
-        assert_eq!(super::p2("C200B40A82"), 3);
-        assert_eq!(super::p2("04005AC33890"), 54);
-        assert_eq!(super::p2("880086C3E88112"), 7);
-        assert_eq!(super::p2("CE00C43D881120"), 9);
-        assert_eq!(super::p2("D8005AC2A8F0"), 1);
-        assert_eq!(super::p2("F600BC2D8F"), 0);
-        assert_eq!(super::p2("9C005AC2F8F0"), 0);
-        assert_eq!(super::p2("9C0141080250320F1802104A08"), 1);
+        assert_eq!(super::p2("C200B40A82"), Ok(3));
+        assert_eq!(super::p2("04005AC33890"), Ok(54));
+        assert_eq!(super::p2("880086C3E88112"), Ok(7));
+        assert_eq!(super::p2("CE00C43D881120"), Ok(9));
+        assert_eq!(super::p2("D8005AC2A8F0"), Ok(1));
+        assert_eq!(super::p2("F600BC2D8F"), Ok(0));
+        assert_eq!(super::p2("9C005AC2F8F0"), Ok(0));
+        assert_eq!(super::p2("9C0141080250320F1802104A08"), Ok(1));
+
+        let input = input(DAY);
+        assert_eq!(super::p2(&input), Ok(470949537659));
+    }
+
+    #[cfg(all(feature = "bitvec", feature = "nom"))]
+    const EXAMPLES: [&str; 15] = [
+        "D2FE28",
+        "38006F45291200",
+        "EE00D40C823060",
+        "8A004A801A8002F478",
+        "620080001611562C8802118E34",
+        "C0015000016115A2E0802F182340",
+        "A0016C880162017C3686B18A3D4780",
+        "C200B40A82",
+        "04005AC33890",
+        "880086C3E88112",
+        "CE00C43D881120",
+        "D8005AC2A8F0",
+        "F600BC2D8F",
+        "9C005AC2F8F0",
+        "9C0141080250320F1802104A08",
+    ];
 
+    #[test]
+    #[cfg(all(feature = "bitvec", feature = "nom"))]
+    fn backend_parity() {
         let input = input(DAY);
-        assert_eq!(super::p2(&input), 470949537659);
+        for s in EXAMPLES.iter().copied().chain([input.as_str()]) {
+            let input = Input::from(s);
+            let expected = BACKENDS[0].decode(&input);
+            for backend in &BACKENDS[1..] {
+                assert_eq!(
+                    backend.decode(&input),
+                    expected,
+                    "{} on {}",
+                    backend.name(),
+                    s
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tool() {
+        let transmission = super::tool(&["encode", "sum(1, max(2, 3))"]).unwrap();
+        assert_eq!(super::p2(&transmission), Ok(4));
+        assert_eq!(
+            super::tool(&["encode", "sum(1,"]).unwrap_err(),
+            "expected a number or an operator at column 7"
+        );
+        assert!(super::tool(&["infix"])
+            .unwrap()
+            .starts_with("(((15 + 9 + 12) * "));
+        assert_eq!(
+            super::tool(&["bench", "1"]).unwrap().lines().count(),
+            BACKENDS.len()
+        );
+        assert!(super::tool(&["bench", "0"]).is_err());
+        assert!(super::tool(&["decode"]).is_err());
+    }
+
+    #[test]
+    fn eval_errors() {
+        let packet = |typ| Packet { version: 0, typ };
+        let literal = |v| packet(Type::Literal(v));
+
+        let gt = packet(Type::Operator(
+            Op::GreaterThan,
+            vec![literal(1), literal(2), literal(3)],
+        ));
+        let error = gt.eval().unwrap_err();
+        assert_eq!(
+            error,
+            EvalError::OperandCount {
+                op: Op::GreaterThan,
+                expected: "2",
+                found: 3
+            }
+        );
+        assert_eq!(error.to_string(), "gt expects 2 operands but has 3");
+
+        let min = packet(Type::Operator(Op::Minimum, vec![]));
+        assert!(matches!(
+            min.eval(),
+            Err(EvalError::OperandCount {
+                op: Op::Minimum,
+                ..
+            })
+        ));
+
+        let product = packet(Type::Operator(
+            Op::Product,
+            vec![literal(u64::MAX), literal(2)],
+        ));
+        assert_eq!(product.eval(), Err(EvalError::Overflow(Op::Product)));
+
+        let sum = packet(Type::Operator(Op::Sum, vec![literal(u64::MAX), literal(0)]));
+        assert_eq!(sum.eval(), Ok(u64::MAX));
+
+        let transmission = gt.encode(None).unwrap();
+        assert_eq!(
+            super::p2(&transmission).unwrap_err().to_string(),
+            "gt expects 2 operands but has 3"
+        );
     }
 }
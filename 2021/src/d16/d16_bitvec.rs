@@ -47,7 +47,7 @@ impl From<&mut Bits> for Packet {
                     }
                 }
 
-                let n_nibbles = nibbles.iter().count();
+                let n_nibbles = nibbles.len();
                 let literal = nibbles
                     .iter()
                     .enumerate()
@@ -63,17 +63,11 @@ impl From<&mut Bits> for Packet {
                 match length_typeid {
                     false => {
                         let total_length: usize = bits.take(WIDTH_TOTAL_LENGTH);
+                        let end = bits.index + total_length;
 
-                        let mut parsed_length = 0;
                         let mut packets: Vec<Packet> = Vec::new();
-                        loop {
-                            let index = bits.index;
+                        while bits.index < end {
                             packets.push(Packet::from(&mut *bits));
-                            let packet_length = bits.index - index;
-                            parsed_length += packet_length;
-                            if parsed_length == total_length {
-                                break;
-                            }
                         }
 
                         Type::Operator(op, packets)
@@ -94,6 +88,18 @@ impl From<&mut Bits> for Packet {
     }
 }
 
+pub struct BitvecDecoder;
+
+impl Decoder for BitvecDecoder {
+    fn name(&self) -> &'static str {
+        "bitvec"
+    }
+
+    fn decode(&self, input: &Input) -> Packet {
+        Packet::from(&mut Bits::from(input))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
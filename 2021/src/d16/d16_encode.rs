@@ -0,0 +1,259 @@
+use std::fmt;
+
+use super::*;
+
+/// How an operator packet records the extent of its sub-packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// The total length in bits of the sub-packets.
+    TotalLength,
+    /// The number of sub-packets.
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    Version(u8),
+    TotalLength(usize),
+    Count(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Version(version) => write!(
+                f,
+                "version {} does not fit in {} bits",
+                version, WIDTH_VERSION
+            ),
+            Self::TotalLength(length) => write!(
+                f,
+                "sub-packet length {} does not fit in {} bits",
+                length, WIDTH_TOTAL_LENGTH
+            ),
+            Self::Count(count) => write!(
+                f,
+                "sub-packet count {} does not fit in {} bits",
+                count, WIDTH_NUM_SUB_PKT
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u64, width: usize) {
+        self.bits
+            .extend((0..width).rev().map(|bit_idx| value >> bit_idx & 1 == 1));
+    }
+
+    fn append(&mut self, other: BitWriter) {
+        self.bits.extend(other.bits);
+    }
+
+    /// Pads with zeros to a whole number of bytes and returns the bits as upper case hex.
+    fn to_hex(&self) -> String {
+        self.bits
+            .chunks(8)
+            .flat_map(|byte| {
+                let byte = (0..8).fold(0u8, |acc, bit_idx| {
+                    acc << 1 | byte.get(bit_idx).copied().unwrap_or(false) as u8
+                });
+                [byte >> 4, byte & 0xf]
+            })
+            .map(|nibble| {
+                std::char::from_digit(nibble as u32, 16)
+                    .unwrap()
+                    .to_ascii_uppercase()
+            })
+            .collect()
+    }
+}
+
+impl Packet {
+    /// Encodes the packet as a hex transmission padded to a whole number of bytes.
+    ///
+    /// Each operator uses the sub-packet count unless it has too many sub-packets to count, in
+    /// which case it uses the total length.  `length_type` forces the same length type for every
+    /// operator instead.
+    pub fn encode(&self, length_type: Option<LengthType>) -> Result<String, EncodeError> {
+        let mut writer = BitWriter::default();
+        self.write(&mut writer, length_type)?;
+        Ok(writer.to_hex())
+    }
+
+    fn write(
+        &self,
+        writer: &mut BitWriter,
+        length_type: Option<LengthType>,
+    ) -> Result<(), EncodeError> {
+        if self.version >> WIDTH_VERSION != 0 {
+            return Err(EncodeError::Version(self.version));
+        }
+        writer.push(self.version.into(), WIDTH_VERSION);
+
+        match &self.typ {
+            Type::Literal(value) => {
+                writer.push(4, WIDTH_TYPEID);
+
+                let n_nibbles = (64 - value.leading_zeros() as usize).max(1).div_ceil(4);
+                for nibble_idx in (0..n_nibbles).rev() {
+                    let more = nibble_idx > 0;
+                    writer.push(more as u64, 1);
+                    writer.push(value >> (4 * nibble_idx) & 0xf, 4);
+                }
+            }
+            Type::Operator(op, children) => {
+                writer.push(u8::from(*op).into(), WIDTH_TYPEID);
+
+                let mut sub_packets = BitWriter::default();
+                for child in children {
+                    child.write(&mut sub_packets, length_type)?;
+                }
+
+                let length_type =
+                    length_type.unwrap_or(if children.len() >> WIDTH_NUM_SUB_PKT == 0 {
+                        LengthType::Count
+                    } else {
+                        LengthType::TotalLength
+                    });
+                match length_type {
+                    LengthType::TotalLength => {
+                        let length = sub_packets.bits.len();
+                        if length >> WIDTH_TOTAL_LENGTH != 0 {
+                            return Err(EncodeError::TotalLength(length));
+                        }
+                        writer.push(0, WIDTH_LENGTH_TYPEID);
+                        writer.push(length as u64, WIDTH_TOTAL_LENGTH);
+                    }
+                    LengthType::Count => {
+                        if children.len() >> WIDTH_NUM_SUB_PKT != 0 {
+                            return Err(EncodeError::Count(children.len()));
+                        }
+                        writer.push(1, WIDTH_LENGTH_TYPEID);
+                        writer.push(children.len() as u64, WIDTH_NUM_SUB_PKT);
+                    }
+                }
+
+                writer.append(sub_packets);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+    const OPS: [Op; 7] = [
+        Op::Sum,
+        Op::Product,
+        Op::Minimum,
+        Op::Maximum,
+        Op::GreaterThan,
+        Op::LessThan,
+        Op::EqualTo,
+    ];
+
+    fn arbitrary_packet(g: &mut Gen, depth: usize) -> Packet {
+        let version = u8::arbitrary(g) % 8;
+        let typ = if depth == 0 || bool::arbitrary(g) {
+            Type::Literal(u64::arbitrary(g))
+        } else {
+            let op = *g.choose(&OPS).unwrap();
+            let n_children = usize::arbitrary(g) % 5;
+            let children = (0..n_children)
+                .map(|_| arbitrary_packet(g, depth - 1))
+                .collect();
+            Type::Operator(op, children)
+        };
+
+        Packet { version, typ }
+    }
+
+    impl Arbitrary for Packet {
+        fn arbitrary(g: &mut Gen) -> Self {
+            arbitrary_packet(g, 4)
+        }
+
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            match &self.typ {
+                Type::Literal(_) => Box::new(std::iter::empty()),
+                Type::Operator(_, children) => Box::new(children.clone().into_iter()),
+            }
+        }
+    }
+
+    fn round_trips(packet: Packet) -> bool {
+        [None, Some(LengthType::TotalLength), Some(LengthType::Count)]
+            .iter()
+            .all(|&length_type| {
+                let input = Input::from(packet.encode(length_type).unwrap().as_str());
+                BACKENDS
+                    .iter()
+                    .all(|backend| backend.decode(&input) == packet)
+            })
+    }
+
+    #[test]
+    fn round_trip() {
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(round_trips as fn(Packet) -> bool);
+    }
+
+    #[test]
+    fn encode_examples() {
+        assert_eq!(decode("D2FE28").encode(None).unwrap(), "D2FE28");
+        assert_eq!(
+            decode("38006F45291200")
+                .encode(Some(LengthType::TotalLength))
+                .unwrap(),
+            "38006F45291200"
+        );
+        assert_eq!(
+            decode("EE00D40C823060").encode(None).unwrap(),
+            "EE00D40C823060"
+        );
+        assert_eq!(
+            decode("38006F45291200")
+                .encode(Some(LengthType::Count))
+                .unwrap(),
+            decode("38006F45291200").encode(None).unwrap()
+        );
+    }
+
+    #[test]
+    fn encode_errors() {
+        let literal = Packet {
+            version: 8,
+            typ: Type::Literal(0),
+        };
+        assert_eq!(literal.encode(None), Err(EncodeError::Version(8)));
+
+        let children = vec![
+            Packet {
+                version: 0,
+                typ: Type::Literal(0),
+            };
+            2048
+        ];
+        let operator = Packet {
+            version: 0,
+            typ: Type::Operator(Op::Sum, children),
+        };
+        assert_eq!(
+            operator.encode(Some(LengthType::Count)),
+            Err(EncodeError::Count(2048))
+        );
+        assert_eq!(decode(&operator.encode(None).unwrap()), operator);
+    }
+}
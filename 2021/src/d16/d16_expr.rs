@@ -0,0 +1,275 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::*;
+
+const OPS: [Op; 7] = [
+    Op::Sum,
+    Op::Product,
+    Op::Minimum,
+    Op::Maximum,
+    Op::GreaterThan,
+    Op::LessThan,
+    Op::EqualTo,
+];
+
+impl Op {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sum => "sum",
+            Self::Product => "product",
+            Self::Minimum => "min",
+            Self::Maximum => "max",
+            Self::GreaterThan => "gt",
+            Self::LessThan => "lt",
+            Self::EqualTo => "eq",
+        }
+    }
+
+    fn infix(self) -> Option<&'static str> {
+        match self {
+            Self::Sum => Some("+"),
+            Self::Product => Some("*"),
+            Self::GreaterThan => Some(">"),
+            Self::LessThan => Some("<"),
+            Self::EqualTo => Some("=="),
+            Self::Minimum | Self::Maximum => None,
+        }
+    }
+}
+
+/// Renders the packet as a call expression such as `sum(3, min(7, 8))`.
+///
+/// Versions are not rendered.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.typ {
+            Type::Literal(v) => write!(f, "{}", v),
+            Type::Operator(op, children) => {
+                write!(f, "{}(", op.name())?;
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", child)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Packet {
+    /// Renders the packet as an infix expression such as `(3 + min(7, 8))`.
+    ///
+    /// Operators with an infix form and at least two operands are parenthesised.  The others
+    /// are rendered as calls.
+    pub fn to_infix(&self) -> String {
+        match &self.typ {
+            Type::Literal(v) => v.to_string(),
+            Type::Operator(op, children) => {
+                let operands = children.iter().map(Packet::to_infix).collect::<Vec<_>>();
+                match op.infix() {
+                    Some(symbol) if operands.len() >= 2 => {
+                        format!("({})", operands.join(&format!(" {} ", symbol)))
+                    }
+                    _ => format!("{}({})", op.name(), operands.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    column: usize,
+    message: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+/// Parses a call expression as rendered by `Display`.  Every packet is given version 0.
+impl FromStr for Packet {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { s, pos: 0 };
+        let packet = parser.expr()?;
+        parser.skip_whitespace();
+        if parser.pos != s.len() {
+            return Err(parser.error("expected end of expression"));
+        }
+        Ok(packet)
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> ExprError {
+        ExprError {
+            column: self.pos + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let rest = self.rest();
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<Packet, ExprError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if !digits.is_empty() {
+            let value = digits.parse().map_err(|_| ExprError {
+                column: start + 1,
+                message: "literal does not fit in 64 bits".to_string(),
+            })?;
+            return Ok(Packet {
+                version: 0,
+                typ: Type::Literal(value),
+            });
+        }
+
+        let name = self.take_while(|c| c.is_ascii_alphabetic());
+        if name.is_empty() {
+            return Err(self.error("expected a number or an operator"));
+        }
+        let op = OPS
+            .iter()
+            .copied()
+            .find(|op| op.name() == name)
+            .ok_or_else(|| ExprError {
+                column: start + 1,
+                message: format!("unknown operator '{}'", name),
+            })?;
+
+        if !self.eat('(') {
+            return Err(self.error("expected '('"));
+        }
+        let mut children = Vec::new();
+        if !self.eat(')') {
+            loop {
+                children.push(self.expr()?);
+                if self.eat(')') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return Err(self.error("expected ',' or ')'"));
+                }
+            }
+        }
+
+        Ok(Packet {
+            version: 0,
+            typ: Type::Operator(op, children),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(decode("C200B40A82").to_string(), "sum(1, 2)");
+        assert_eq!(
+            decode("9C0141080250320F1802104A08").to_string(),
+            "eq(sum(1, 3), product(2, 2))"
+        );
+        assert_eq!(decode("D2FE28").to_string(), "2021");
+    }
+
+    #[test]
+    fn to_infix() {
+        assert_eq!(
+            decode("9C0141080250320F1802104A08").to_infix(),
+            "((1 + 3) == (2 * 2))"
+        );
+        assert_eq!(decode("880086C3E88112").to_infix(), "min(7, 8, 9)");
+        assert_eq!(
+            "sum(3, min(7, 8))".parse::<Packet>().unwrap().to_infix(),
+            "(3 + min(7, 8))"
+        );
+        assert_eq!(
+            "product(4)".parse::<Packet>().unwrap().to_infix(),
+            "product(4)"
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let packet = " sum( 3,min(7 , 8) ) ".parse::<Packet>().unwrap();
+        assert_eq!(packet.to_string(), "sum(3, min(7, 8))");
+        assert_eq!(packet.eval(), Ok(10));
+        assert_eq!("max()".parse::<Packet>().unwrap().to_string(), "max()");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |s: &str| s.parse::<Packet>().unwrap_err().to_string();
+        assert_eq!(
+            error("sum(1, avg(2))"),
+            "unknown operator 'avg' at column 8"
+        );
+        assert_eq!(error("sum(1 2)"), "expected ',' or ')' at column 7");
+        assert_eq!(error("sum"), "expected '(' at column 4");
+        assert_eq!(error("(1)"), "expected a number or an operator at column 1");
+        assert_eq!(error("1 2"), "expected end of expression at column 3");
+        assert_eq!(
+            error("99999999999999999999"),
+            "literal does not fit in 64 bits at column 1"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let packet = decode(&input(DAY));
+        let parsed = packet.to_string().parse::<Packet>().unwrap();
+        assert_eq!(parsed.to_string(), packet.to_string());
+        let transmission = parsed.encode(None).unwrap();
+        assert_eq!(decode(&transmission).eval(), packet.eval());
+    }
+
+    #[test]
+    fn hand_written_transmission() {
+        let packet = "gt(product(6, 7), sum(40, 1))".parse::<Packet>().unwrap();
+        let transmission = packet.encode(None).unwrap();
+        assert_eq!(p2(&transmission), Ok(1));
+    }
+}
@@ -2,10 +2,7 @@ use nom::{
     IResult,
     bits::{
         bits,
-        complete::{
-            take,
-            tag,
-        },
+        complete::take,
     },
     sequence::tuple,
 };
@@ -30,7 +27,7 @@ fn parse_packet_type_literal(input: (&[u8], usize)) -> IResult<(&[u8], usize), T
         }
     }
 
-    let n_nibbles = nibbles.iter().count();
+    let n_nibbles = nibbles.len();
     let literal = nibbles
         .iter()
         .enumerate()
@@ -53,7 +50,7 @@ fn parse_packet_type_operator(input: (&[u8], usize), op: Op) -> IResult<(&[u8],
             let mut parsed_length = 0;
             let mut packets: Vec<Packet> = Vec::new();
             let mut input = input;
-            loop {
+            while parsed_length < total_length {
                 let bit_remaining_before = 8 * input.0.len() - input.1;
                 let packet;
                 (input, packet) = parse_packet_bits(input)?;
@@ -61,9 +58,6 @@ fn parse_packet_type_operator(input: (&[u8], usize), op: Op) -> IResult<(&[u8],
                 let bits_remaining_after = 8 * input.0.len() - input.1;
                 let packet_length = bit_remaining_before - bits_remaining_after;
                 parsed_length += packet_length;
-                if parsed_length == total_length {
-                    break;
-                }
             }
 
             Ok((input, Type::Operator(op, packets)))
@@ -103,6 +97,18 @@ impl From<&[u8]> for Packet {
     }
 }
 
+pub struct NomDecoder;
+
+impl Decoder for NomDecoder {
+    fn name(&self) -> &'static str {
+        "nom"
+    }
+
+    fn decode(&self, input: &Input) -> Packet {
+        Packet::from(input.bytes.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
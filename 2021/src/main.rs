@@ -18,11 +18,87 @@ mod d12;
 mod d13;
 mod d14;
 mod d16;
+mod matrix;
+mod output;
+
+use output::{Format, Solution};
+
+const USAGE: &str = "\
+usage: aoc2021 [--format text|json|csv]
+       aoc2021 DAY TOOL [ARG]...
+
+tools:
+    4 never-winning [--diagonals]
+                    list the bingo boards that never get a line
+    5 orientations  count the overlapping points of lines of each orientation
+    5 heat-map      draw how many lines cover each point
+    7 optimise linear|triangular|quadratic
+                    find the cheapest position to align the crabs to under a cost model
+    8 decode decimal|hex [ENTRY]
+                    read the output of an entry, or of every entry of the input
+    9 render        colour each basin of the heightmap for the terminal
+    9 ppm           write the basins as a plain PPM image
+    10 fix          complete every incomplete line of the navigation subsystem
+    11 cycle        find when the octopus grid starts repeating
+    11 at STEP      print the octopus grid after any number of steps
+    11 frames STEPS DIR [SCALE]
+                    save the grid after each step as text, or as PPM images when scaled
+    12 paths [--revisit]
+                    list every path through the caves
+    14 expand STEPS print the polymer itself after a few steps
+    16 encode EXPR  encode a call expression such as `sum(1, max(2, 3))` as a transmission
+    16 infix        render the transmission as an infix expression
+    16 bench [ROUNDS]
+                    time each backend decoding the transmission, 1000 rounds by default";
+
+/// One of a day's extra commands, which takes the command and its arguments and returns the
+/// output to print.
+type Tool = fn(&[&str]) -> Result<String, String>;
+
+const TOOLS: [(usize, Tool); 10] = [
+    (4, d04::tool),
+    (5, d05::tool),
+    (7, d07::tool),
+    (8, d08::tool),
+    (9, d09::tool),
+    (10, d10::tool),
+    (11, d11::tool),
+    (12, d12::tool),
+    (14, d14::tool),
+    (16, d16::tool),
+];
+
+enum Command {
+    Solve(Format),
+    Tool(Tool, Vec<String>),
+}
 
 fn main() {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let format = match command {
+        Command::Solve(format) => format,
+        Command::Tool(tool, args) => {
+            let args = args.iter().map(String::as_str).collect::<Vec<&str>>();
+            match tool(&args) {
+                Ok(output) => print!("{}", output),
+                Err(message) => {
+                    eprintln!("{}", message);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+    };
+
     let time = std::time::Instant::now();
 
-    let days: Vec<Box<dyn Fn() -> String + Send + Sync>> = vec![
+    let days: Vec<Box<dyn Fn() -> Vec<Solution> + Send + Sync>> = vec![
         Box::new(d01::run),
         Box::new(d02::run),
         Box::new(d03::run),
@@ -40,14 +116,46 @@ fn main() {
         Box::new(d16::run),
     ];
 
-    let output = days.par_iter()
-        .map(|day| day())
-        .collect::<Vec<String>>()
-        .join("");
+    let solutions = days
+        .par_iter()
+        .flat_map(|day| day())
+        .collect::<Vec<Solution>>();
+
+    print!("{}", format.render(&solutions));
+
+    if format == Format::Text {
+        println!("total {:?}", time.elapsed());
+    }
+}
+
+fn parse_args<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut format = Format::Text;
+    let mut args = args.into_iter().peekable();
+
+    if let Some(day) = args.peek().and_then(|arg| arg.parse::<usize>().ok()) {
+        let tool = TOOLS
+            .iter()
+            .find(|&&(tool_day, _)| tool_day == day)
+            .map(|&(_, tool)| tool)
+            .ok_or_else(|| format!("day {} has no tools", day))?;
+        return Ok(Command::Tool(tool, args.skip(1).collect()));
+    }
 
-    println!("{}", output);
+    while let Some(arg) = args.next() {
+        let value = match arg.split_once('=') {
+            Some(("--format", value)) => value.to_string(),
+            None if arg == "--format" => args
+                .next()
+                .ok_or_else(|| "missing value for --format".to_string())?,
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        };
+        format = Format::try_from(value.as_str())?;
+    }
 
-    println!("total {:?}", time.elapsed());
+    Ok(Command::Solve(format))
 }
 
 fn load_file<P>(path: P) -> String
@@ -0,0 +1,105 @@
+use num_bigint::BigUint;
+
+/// A square matrix of arbitrary-precision counts, for advancing linear recurrences any number
+/// of steps at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    cells: Vec<Vec<BigUint>>,
+}
+
+impl Matrix {
+    pub fn zero(n: usize) -> Self {
+        Self {
+            cells: vec![vec![BigUint::default(); n]; n],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut identity = Self::zero(n);
+        for i in 0..n {
+            identity.increment(i, i);
+        }
+
+        identity
+    }
+
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn increment(&mut self, row: usize, col: usize) {
+        self.cells[row][col] += 1u8;
+    }
+
+    /// Skips the zero entries of `self`, which most transition matrices are made of.
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut product = Self::zero(self.size());
+        for (i, row) in self.cells.iter().enumerate() {
+            let nonzero = row
+                .iter()
+                .enumerate()
+                .filter(|(_, a)| **a != BigUint::default());
+            for (k, a) in nonzero {
+                for (j, b) in other.cells[k].iter().enumerate() {
+                    product.cells[i][j] += a * b;
+                }
+            }
+        }
+
+        product
+    }
+
+    /// Raises the matrix to a power by repeated squaring.
+    pub fn pow(&self, mut exp: u64) -> Self {
+        let mut result = Self::identity(self.size());
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base);
+            }
+        }
+
+        result
+    }
+
+    /// Multiplies the matrix by a column vector.
+    pub fn apply<T>(&self, v: &[T]) -> Vec<BigUint>
+    where
+        T: Copy + Into<BigUint>,
+    {
+        self.cells
+            .iter()
+            .map(|row| row.iter().zip(v).map(|(a, &b)| a * b.into()).sum())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci() {
+        let mut step = Matrix::zero(2);
+        step.increment(0, 0);
+        step.increment(0, 1);
+        step.increment(1, 0);
+
+        let fib = |n| step.pow(n).apply(&[0u8, 1])[0].clone();
+        assert_eq!(fib(0), BigUint::default());
+        assert_eq!(fib(10), BigUint::from(55u8));
+        assert_eq!(fib(300), fib(299) + fib(298));
+        assert_eq!(
+            step.pow(5),
+            (0..5).fold(Matrix::identity(2), |m, _| m.mul(&step))
+        );
+        assert_eq!(
+            step.pow(10).apply(&[1u64, 0]),
+            vec![BigUint::from(89u8), BigUint::from(55u8)]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The answer to one part of a day along with how long it took to find.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub day: usize,
+    pub part: usize,
+    pub answer: String,
+    pub duration: Duration,
+}
+
+impl Solution {
+    pub fn time<F, T>(day: usize, part: usize, f: F) -> Self
+    where
+        F: FnOnce() -> T,
+        T: ToString,
+    {
+        let time = Instant::now();
+        let answer = f().to_string();
+        let duration = time.elapsed();
+
+        Self {
+            day,
+            part,
+            answer,
+            duration,
+        }
+    }
+
+    /// Like `time` for a part that can fail, in which case the error is reported as the answer.
+    pub fn try_time<F, T, E>(day: usize, part: usize, f: F) -> Self
+    where
+        F: FnOnce() -> Result<T, E>,
+        T: ToString,
+        E: fmt::Display,
+    {
+        Self::time(day, part, || match f() {
+            Ok(answer) => answer.to_string(),
+            Err(error) => format!("error: {}", error),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl TryFrom<&str> for Format {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("unknown format '{}'", s)),
+        }
+    }
+}
+
+impl Format {
+    pub fn render(self, solutions: &[Solution]) -> String {
+        match self {
+            Self::Text => text(solutions),
+            Self::Json => json(solutions),
+            Self::Csv => csv(solutions),
+        }
+    }
+}
+
+/// Renders a table with one row per part.  Answers that span multiple lines continue below
+/// their row.
+fn text(solutions: &[Solution]) -> String {
+    let mut output = String::new();
+    writeln!(output, "{:<6} {:>12}  answer", "part", "time").unwrap();
+    for solution in solutions {
+        let name = format!("d{:02}p{}", solution.day, solution.part);
+        let duration = format!("{:.3?}", solution.duration);
+        let mut lines = solution.answer.lines();
+        writeln!(
+            output,
+            "{:<6} {:>12}  {}",
+            name,
+            duration,
+            lines.next().unwrap_or("")
+        )
+        .unwrap();
+        for line in lines {
+            writeln!(output, "{:<6} {:>12}  {}", "", "", line).unwrap();
+        }
+    }
+    output
+}
+
+/// Renders an array of objects with the duration in nanoseconds.
+fn json(solutions: &[Solution]) -> String {
+    let objects = solutions
+        .iter()
+        .map(|solution| {
+            format!(
+                "  {{\"day\": {}, \"part\": {}, \"answer\": \"{}\", \"duration_ns\": {}}}",
+                solution.day,
+                solution.part,
+                json_escape(&solution.answer),
+                solution.duration.as_nanos()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    s.chars().fold(String::new(), |mut escaped, c| {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => escaped += "\\r",
+            '\t' => escaped += "\\t",
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+        escaped
+    })
+}
+
+/// Renders a header row followed by one record per part with the duration in nanoseconds.
+fn csv(solutions: &[Solution]) -> String {
+    let mut output = String::from("day,part,answer,duration_ns\n");
+    for solution in solutions {
+        writeln!(
+            output,
+            "{},{},{},{}",
+            solution.day,
+            solution.part,
+            csv_escape(&solution.answer),
+            solution.duration.as_nanos()
+        )
+        .unwrap();
+    }
+    output
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, Solution};
+
+    use indoc::indoc;
+
+    use std::time::Duration;
+
+    fn solutions() -> Vec<Solution> {
+        vec![
+            Solution {
+                day: 1,
+                part: 1,
+                answer: "1462".into(),
+                duration: Duration::from_micros(15),
+            },
+            Solution {
+                day: 13,
+                part: 2,
+                answer: "#..#\n\"ab\",c".into(),
+                duration: Duration::from_millis(2),
+            },
+        ]
+    }
+
+    #[test]
+    fn time() {
+        let solution = Solution::time(7, 2, || 42);
+        assert_eq!(solution.day, 7);
+        assert_eq!(solution.part, 2);
+        assert_eq!(solution.answer, "42");
+
+        let solution = Solution::try_time(7, 2, || Ok::<_, String>(42));
+        assert_eq!(solution.answer, "42");
+        let solution = Solution::try_time(7, 2, || Err::<u64, _>("no answer"));
+        assert_eq!(solution.answer, "error: no answer");
+    }
+
+    #[test]
+    fn text() {
+        let expected = indoc! {r##"
+            part           time  answer
+            d01p1      15.000µs  1462
+            d13p2       2.000ms  #..#
+                                 "ab",c
+        "##};
+        assert_eq!(Format::Text.render(&solutions()), expected);
+    }
+
+    #[test]
+    fn json() {
+        let expected = indoc! {r##"
+            [
+              {"day": 1, "part": 1, "answer": "1462", "duration_ns": 15000},
+              {"day": 13, "part": 2, "answer": "#..#\n\"ab\",c", "duration_ns": 2000000}
+            ]
+        "##};
+        assert_eq!(Format::Json.render(&solutions()), expected);
+        assert_eq!(Format::Json.render(&[]), "[]\n");
+    }
+
+    #[test]
+    fn csv() {
+        let expected = indoc! {r##"
+            day,part,answer,duration_ns
+            1,1,1462,15000
+            13,2,"#..#
+            ""ab"",c",2000000
+        "##};
+        assert_eq!(Format::Csv.render(&solutions()), expected);
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(Format::try_from("csv"), Ok(Format::Csv));
+        assert!(Format::try_from("xml").is_err());
+    }
+}
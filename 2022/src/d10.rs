@@ -107,14 +107,17 @@ fn p1(input: &Input) -> i32 {
         .cummulative_signal_strength
 }
 
+/// Reads the letters on the CRT, or shows the CRT if they are not letters.
 #[aoc(day10, part2)]
 fn p2(input: &Input) -> String {
-    input
+    let frame_buffer = input
         .iter()
         .fold(State::new(), |state, instr| {
             state.next(instr)
         })
-        .frame_buffer
+        .frame_buffer;
+
+    aoc_ocr::recognise_art(&frame_buffer, '#').unwrap_or(frame_buffer)
 }
 
 #[cfg(test)]
@@ -292,5 +295,10 @@ mod test {
         "};
         assert_eq!(p2(&parse(INPUT)), expected);
     }
-}
 
+    #[test]
+    fn test_p2_input() {
+        let input = std::fs::read_to_string("input/2022/day10.txt").unwrap();
+        assert_eq!(p2(&parse(&input)), "ZGCJZJFL");
+    }
+}
@@ -0,0 +1,213 @@
+//! Reads the capital letters that some puzzles draw on a dot matrix.
+//!
+//! Two fonts turn up: letters 4 pixels wide and 6 tall, and letters 6 pixels wide and 10
+//! tall.  The font is chosen by the height of the lit area, and letters are told apart by the
+//! unlit columns between them.
+
+use std::fmt;
+
+const FONT_4X6: &[(char, &[&str])] = &[
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[rustfmt::skip]
+const FONT_6X10: &[(char, &[&str])] = &[
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Nothing is lit.
+    Empty,
+    /// The lit rows are neither 6 nor 10 tall.
+    Height(usize),
+    /// The letter starting at this column, counted from 0, is not in the font.
+    UnknownGlyph { column: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "no pixels are lit"),
+            Error::Height(height) => {
+                write!(f, "letters are {} pixels tall, expected 6 or 10", height)
+            }
+            Error::UnknownGlyph { column } => write!(f, "unknown letter at column {}", column),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads the letters in a grid of rows of pixels, where `true` is lit.
+///
+/// Unlit rows and columns around the letters are ignored, so the grid can have any margin.
+pub fn recognise<R>(rows: &[R]) -> Result<String, Error>
+where
+    R: AsRef<[bool]>,
+{
+    let rows = rows.iter().map(AsRef::as_ref).collect::<Vec<&[bool]>>();
+    let is_lit = |&row: &&[bool]| row.iter().any(|&pixel| pixel);
+    let top = rows.iter().position(is_lit).ok_or(Error::Empty)?;
+    let bottom = rows.iter().rposition(is_lit).unwrap();
+    let rows = &rows[top..=bottom];
+
+    let font = match rows.len() {
+        6 => FONT_4X6,
+        10 => FONT_6X10,
+        height => return Err(Error::Height(height)),
+    };
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap();
+    let pixel = |x: usize, y: usize| rows[y].get(x).copied().unwrap_or(false);
+    let column_is_lit = |x: usize| (0..rows.len()).any(|y| pixel(x, y));
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if !column_is_lit(x) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < width && column_is_lit(x) {
+            x += 1;
+        }
+
+        let glyph = (0..rows.len())
+            .map(|y| {
+                (start..x)
+                    .map(|x| if pixel(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect::<Vec<String>>();
+        let letter = font
+            .iter()
+            .find(|(_, pattern)| trim_columns(pattern) == glyph)
+            .ok_or(Error::UnknownGlyph { column: start })?
+            .0;
+        letters.push(letter);
+    }
+
+    Ok(letters)
+}
+
+/// Reads the letters in text art where `lit` marks a lit pixel and anything else is unlit.
+pub fn recognise_art(art: &str, lit: char) -> Result<String, Error> {
+    let rows = art
+        .lines()
+        .map(|line| line.chars().map(|c| c == lit).collect())
+        .collect::<Vec<Vec<bool>>>();
+
+    recognise(&rows)
+}
+
+/// Drops the unlit columns on either side of a font glyph.
+fn trim_columns(pattern: &[&str]) -> Vec<String> {
+    let width = pattern[0].len();
+    let lit = |x: usize| pattern.iter().any(|row| row.as_bytes()[x] == b'#');
+    let start = (0..width).find(|&x| lit(x)).unwrap();
+    let end = (0..width).rfind(|&x| lit(x)).unwrap();
+
+    pattern
+        .iter()
+        .map(|row| row[start..=end].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes out every letter of a font in order, separated by `gap`.
+    fn alphabet(font: &[(char, &[&str])], gap: &str) -> (String, String) {
+        let height = font[0].1.len();
+        let art = (0..height)
+            .map(|y| {
+                font.iter()
+                    .map(|(_, pattern)| pattern[y])
+                    .collect::<Vec<&str>>()
+                    .join(gap)
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let letters = font.iter().map(|&(letter, _)| letter).collect();
+
+        (art, letters)
+    }
+
+    #[test]
+    fn small_font() {
+        let (art, letters) = alphabet(FONT_4X6, ".");
+        assert_eq!(recognise_art(&art, '#'), Ok(letters));
+        assert_eq!(
+            recognise_art(&art.replace('.', " "), '#').unwrap(),
+            "ABCEFGHIJKLOPRSUYZ"
+        );
+    }
+
+    #[test]
+    fn large_font() {
+        let (art, letters) = alphabet(FONT_6X10, "..");
+        assert_eq!(recognise_art(&art, '#'), Ok(letters));
+    }
+
+    #[test]
+    fn margins() {
+        let rows = vec![
+            vec![false; 12],
+            vec![false, false, true, false, false, true],
+            vec![false, false, true, false, false, true],
+            vec![false, false, true, true, true, true],
+            vec![false, false, true, false, false, true],
+            vec![false, false, true, false, false, true],
+            vec![false, false, true, false, false, true],
+            vec![false; 3],
+        ];
+
+        assert_eq!(recognise(&rows), Ok("H".to_string()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(recognise_art("....\n....", '#'), Err(Error::Empty));
+        assert_eq!(recognise_art("#\n#\n#", '#'), Err(Error::Height(3)));
+
+        let art = "#..#.####\n#..#.#..#\n####.#..#\n#..#.#..#\n#..#.#..#\n#..#.####";
+        let error = recognise_art(art, '#').unwrap_err();
+        assert_eq!(error, Error::UnknownGlyph { column: 5 });
+        assert_eq!(error.to_string(), "unknown letter at column 5");
+    }
+}